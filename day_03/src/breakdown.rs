@@ -0,0 +1,68 @@
+use crate::report::{BitStat, Criterion, Report, Tie};
+use num_bigint::BigUint;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Policies {
+    pub gamma: Criterion,
+    pub epsilon: Criterion,
+    pub oxygen: Criterion,
+    pub co2: Criterion,
+}
+
+// Ties in the rates go to zero for gamma and one for epsilon, which is what
+// the original `ones > len / 2` check did for both odd and even row counts.
+impl Default for Policies {
+    fn default() -> Self {
+        Policies {
+            gamma: Criterion::MostCommon(Tie::Zeros),
+            epsilon: Criterion::LeastCommon(Tie::Ones),
+            oxygen: Criterion::MostCommon(Tie::Ones),
+            co2: Criterion::LeastCommon(Tie::Zeros),
+        }
+    }
+}
+
+pub struct Breakdown {
+    pub gamma: Vec<BitStat>,
+    pub epsilon: Vec<BitStat>,
+    pub oxygen: Vec<BitStat>,
+    pub co2: Vec<BitStat>,
+}
+
+impl Breakdown {
+    pub fn new(report: &Report, policies: &Policies) -> Breakdown {
+        Breakdown {
+            gamma: report.rate(policies.gamma),
+            epsilon: report.rate(policies.epsilon),
+            oxygen: report.rating_trace(policies.oxygen).1,
+            co2: report.rating_trace(policies.co2).1,
+        }
+    }
+}
+
+pub fn to_value(stats: &[BitStat]) -> BigUint {
+    stats
+        .iter()
+        .fold(BigUint::default(), |acc, s| (acc << 1) + s.bit as u32)
+}
+
+impl fmt::Display for Breakdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>4} | {:^16} | {:^16} | {:^16} | {:^16}",
+            "pos", "gamma", "epsilon", "oxygen", "co2"
+        )?;
+        for pos in 0..self.gamma.len() {
+            write!(f, "{:>4}", pos)?;
+            for stats in [&self.gamma, &self.epsilon, &self.oxygen, &self.co2] {
+                let s = stats[pos];
+                let tie = if s.ones == s.zeros { '*' } else { ' ' };
+                write!(f, " | {:>6} {:>6} {}{}", s.ones, s.zeros, s.bit as u8, tie)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
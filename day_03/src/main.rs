@@ -1,116 +1,38 @@
-use std::collections::btree_map::Values;
+mod breakdown;
+mod report;
+mod trie;
+
+use breakdown::{to_value, Breakdown, Policies};
+use num_bigint::BigUint;
+use report::Report;
+use std::env;
 use std::fs;
 use std::time::Instant;
+use trie::Trie;
 
-type Generated = Vec<Vec<u8>>;
+type Generated = Report;
 
 fn generate(input: &str) -> Generated {
-    input
-        .lines()
-        .map(|l| l.trim())
-        .filter(|l| !l.is_empty())
-        .map(|l| {
-            l.chars()
-                .map(|c| match c {
-                    '1' => 1,
-                    '0' => 0,
-                    _ => panic!("Incorrect char"),
-                })
-                .collect()
-        })
-        .collect()
+    let mut lines = input.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+    let first = lines.next().expect("Empty report");
+    let mut report = Report::new(first.len());
+    report.push(first);
+    lines.for_each(|l| report.push(l));
+    report
 }
 
-fn part_1(input: &Generated) -> i32 {
-    let mut values: Vec<usize> = Vec::new();
-    values.resize(input[0].len(), 0);
+fn part_1(input: &Generated) -> BigUint {
+    let policies = Policies::default();
 
-    input.iter().for_each(|v| {
-        v.iter()
-            .enumerate()
-            .for_each(|(n, v)| values[n] += *v as usize)
-    });
-
-    let mut gamma = 0;
-    let mut epsilon = 0;
-
-    values.iter().for_each(|v| {
-        gamma <<= 1;
-        epsilon <<= 1;
-        match *v > input.len() / 2 {
-            true => {
-                gamma += 1;
-            }
-            false => {
-                epsilon += 1;
-            }
-        };
-    });
-
-    gamma * epsilon
+    to_value(&input.rate(policies.gamma)) * to_value(&input.rate(policies.epsilon))
 }
 
-fn part_2(input: &Generated) -> i32 {
-    let mut oxy = input.clone();
-    let mut co2 = input.clone();
-
-    let mut oxy_val = 0;
-    let mut co2_val = 0;
-
-    //I will clean that up one day...
-    for i in 0..input[0].len() {
-        let mut temp1: Generated = Vec::new();
-        let mut temp2: Generated = Vec::new();
-
-        co2.iter().for_each(|item| {
-            if item[i] == 1 {
-                temp1.push(item.clone())
-            } else {
-                temp2.push(item.clone())
-            }
-        });
-
-        match temp1.len() < temp2.len() {
-            true => co2 = temp1,
-            false => co2 = temp2,
-        }
-
-        if co2.len() == 1 {
-            co2[0].iter().for_each(|v| {
-                co2_val <<= 1;
-                co2_val += *v as i32;
-            });
-            break;
-        }
-    }
-
-    for i in 0..input[0].len() {
-        let mut temp1: Generated = Vec::new();
-        let mut temp2: Generated = Vec::new();
+fn part_2(input: &Generated) -> BigUint {
+    let policies = Policies::default();
+    let oxy = input.rating(policies.oxygen);
+    let co2 = input.rating(policies.co2);
 
-        oxy.iter().for_each(|item| {
-            if item[i] == 1 {
-                temp1.push(item.clone())
-            } else {
-                temp2.push(item.clone())
-            }
-        });
-
-        match temp1.len() >= temp2.len() {
-            true => oxy = temp1,
-            false => oxy = temp2,
-        }
-
-        if oxy.len() == 1 {
-            oxy[0].iter().for_each(|v| {
-                oxy_val <<= 1;
-                oxy_val += *v as i32;
-            });
-            break;
-        }
-    }
-
-    oxy_val * co2_val
+    input.value(oxy) * input.value(co2)
 }
 
 fn main() {
@@ -136,22 +58,53 @@ fn main() {
         res2,
         res2_stop.duration_since(res2_start)
     );
+
+    // e.g. `--stats`, `--trie --prefix 101 --rank 0`
+    let args = env::args().collect::<Vec<String>>();
+    let arg = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .map(|pos| args.get(pos + 1).expect("Missing argument value").as_str())
+    };
+    if args.iter().any(|a| a == "--stats") {
+        print!("{}", Breakdown::new(&data, &Policies::default()));
+    }
+    if args.iter().any(|a| a == "--trie") {
+        let trie = Trie::from_report(&data);
+        let policies = Policies::default();
+        println!("Oxygen rating: {}", trie.rating(policies.oxygen));
+        println!("CO2 rating: {}", trie.rating(policies.co2));
+        if let Some(prefix) = arg("--prefix") {
+            println!(
+                "Rows starting with {}: {}",
+                prefix,
+                trie.count_prefix(prefix)
+            );
+        }
+        if let Some(k) = arg("--rank") {
+            match trie.most_common(k.parse().unwrap()) {
+                Some((row, count)) => println!("Row {} seen {} times", row, count),
+                None => println!("No row at rank {}", k),
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use report::{Criterion, Tie};
     #[test]
     fn test_part_1() {
         assert_eq!(
-            20450,
+            BigUint::from(20450u32),
             part_1(&generate("000000000001\n000000000100\n000000000101\n"))
         );
     }
     #[test]
     fn test_part_2() {
         assert_eq!(
-            230,
+            BigUint::from(230u32),
             part_2(&generate(
                 "00100\n
         11110\n
@@ -168,4 +121,22 @@ mod tests {
             ))
         );
     }
+    #[test]
+    fn test_breakdown() {
+        let report = generate("10\n01\n11\n00\n");
+        let mut policies = Policies::default();
+        let breakdown = Breakdown::new(&report, &policies);
+        assert_eq!(BigUint::from(0b00u32), to_value(&breakdown.gamma));
+        assert_eq!(BigUint::from(0b11u32), to_value(&breakdown.epsilon));
+        assert_eq!(BigUint::from(0b11u32), to_value(&breakdown.oxygen));
+        assert_eq!(BigUint::from(0b00u32), to_value(&breakdown.co2));
+        assert_eq!((2, 2), (breakdown.gamma[0].ones, breakdown.gamma[0].zeros));
+        assert_eq!((1, 1), (breakdown.co2[1].ones, breakdown.co2[1].zeros));
+
+        policies.gamma = Criterion::MostCommon(Tie::Ones);
+        policies.co2 = Criterion::LeastCommon(Tie::Ones);
+        let breakdown = Breakdown::new(&report, &policies);
+        assert_eq!(BigUint::from(0b11u32), to_value(&breakdown.gamma));
+        assert_eq!(BigUint::from(0b11u32), to_value(&breakdown.co2));
+    }
 }
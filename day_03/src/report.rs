@@ -0,0 +1,197 @@
+use num_bigint::BigUint;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tie {
+    Ones,
+    Zeros,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Criterion {
+    MostCommon(Tie),
+    LeastCommon(Tie),
+}
+
+impl Criterion {
+    pub fn keep_ones(self, ones: usize, zeros: usize) -> bool {
+        match self {
+            Criterion::MostCommon(tie) | Criterion::LeastCommon(tie) if ones == zeros => {
+                tie == Tie::Ones
+            }
+            Criterion::MostCommon(_) => ones > zeros,
+            Criterion::LeastCommon(_) => ones < zeros,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BitStat {
+    pub ones: usize,
+    pub zeros: usize,
+    pub bit: bool,
+}
+
+// Rows are packed into `stride` little-endian u64 words each, column `c`
+// being bit `width - 1 - c`, so a row of up to 64 bits is just its value.
+pub struct Report {
+    width: usize,
+    stride: usize,
+    rows: usize,
+    words: Vec<u64>,
+}
+
+impl Report {
+    pub fn new(width: usize) -> Report {
+        Report {
+            width,
+            stride: width.div_ceil(64),
+            rows: 0,
+            words: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, line: &str) {
+        if line.len() != self.width {
+            panic!("Incorrect line width");
+        }
+        let start = self.words.len();
+        self.words.resize(start + self.stride, 0);
+        line.chars().enumerate().for_each(|(col, c)| match c {
+            '1' => {
+                let bit = self.width - 1 - col;
+                self.words[start + bit / 64] |= 1 << (bit % 64);
+            }
+            '0' => (),
+            _ => panic!("Incorrect char"),
+        });
+        self.rows += 1;
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    fn row(&self, row: usize) -> &[u64] {
+        &self.words[row * self.stride..(row + 1) * self.stride]
+    }
+
+    pub fn bit(&self, row: usize, col: usize) -> bool {
+        let bit = self.width - 1 - col;
+        self.row(row)[bit / 64] >> (bit % 64) & 1 == 1
+    }
+
+    pub fn value(&self, row: usize) -> BigUint {
+        self.row(row)
+            .iter()
+            .rev()
+            .fold(BigUint::default(), |acc, &word| {
+                (acc << 64) | BigUint::from(word)
+            })
+    }
+
+    pub fn column_ones(&self) -> Vec<usize> {
+        let mut ones = vec![0; self.width];
+        self.words.chunks(self.stride).for_each(|row| {
+            row.iter().enumerate().for_each(|(w, &word)| {
+                let mut word = word;
+                while word != 0 {
+                    let bit = w * 64 + word.trailing_zeros() as usize;
+                    ones[self.width - 1 - bit] += 1;
+                    word &= word - 1;
+                }
+            })
+        });
+        ones
+    }
+
+    pub fn rate(&self, criterion: Criterion) -> Vec<BitStat> {
+        self.column_ones()
+            .iter()
+            .map(|&ones| {
+                let zeros = self.rows - ones;
+                BitStat {
+                    ones,
+                    zeros,
+                    bit: criterion.keep_ones(ones, zeros),
+                }
+            })
+            .collect()
+    }
+
+    pub fn rating(&self, criterion: Criterion) -> usize {
+        self.rating_trace(criterion).0
+    }
+
+    // Narrows the candidate rows one column at a time by partitioning an index
+    // range in place. Once a single row is left it is kept at every column, so
+    // the trace still covers the full width.
+    pub fn rating_trace(&self, criterion: Criterion) -> (usize, Vec<BitStat>) {
+        let mut indices: Vec<usize> = (0..self.rows).collect();
+        let (mut lo, mut hi) = (0, self.rows);
+        let mut trace = Vec::with_capacity(self.width);
+
+        for col in 0..self.width {
+            let mid = lo + partition(&mut indices[lo..hi], |&row| self.bit(row, col));
+            let ones = mid - lo;
+            let zeros = hi - mid;
+            let bit = match (ones, zeros) {
+                (0, _) => false,
+                (_, 0) => true,
+                _ => criterion.keep_ones(ones, zeros),
+            };
+            match bit {
+                true => hi = mid,
+                false => lo = mid,
+            }
+            trace.push(BitStat { ones, zeros, bit });
+        }
+
+        (indices[lo], trace)
+    }
+}
+
+// Moves the items matching `pred` to the front, returning how many there are.
+fn partition<T, F>(items: &mut [T], pred: F) -> usize
+where
+    F: Fn(&T) -> bool,
+{
+    let mut split = 0;
+    for i in 0..items.len() {
+        if pred(&items[i]) {
+            items.swap(split, i);
+            split += 1;
+        }
+    }
+    split
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wide_rows() {
+        let mut report = Report::new(130);
+        let mut line = "0".repeat(130);
+        line.replace_range(0..1, "1");
+        line.replace_range(129..130, "1");
+        report.push(&line);
+        report.push(&"0".repeat(130));
+
+        assert!(report.bit(0, 0) && report.bit(0, 129) && !report.bit(0, 64));
+        let ones = report.column_ones();
+        assert_eq!((1, 0, 1), (ones[0], ones[64], ones[129]));
+        assert!(report.rate(Criterion::MostCommon(Tie::Ones))[0].bit);
+        assert!(!report.rate(Criterion::MostCommon(Tie::Zeros))[0].bit);
+        assert_eq!(0, report.rating(Criterion::MostCommon(Tie::Ones)));
+        assert_eq!(1, report.rating(Criterion::LeastCommon(Tie::Zeros)));
+        assert_eq!(
+            (BigUint::from(1u32) << 129) + 1u32,
+            report.value(report.rating(Criterion::MostCommon(Tie::Ones)))
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use crate::report::{Criterion, Report};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+const ROOT: usize = 0;
+const NONE: u32 = 0;
+
+#[derive(Clone, Copy, Default)]
+struct Node {
+    children: [u32; 2],
+    count: usize,
+}
+
+pub struct Trie {
+    width: usize,
+    nodes: Vec<Node>,
+}
+
+impl Trie {
+    pub fn from_report(report: &Report) -> Trie {
+        let mut trie = Trie {
+            width: report.width(),
+            nodes: vec![Node::default()],
+        };
+
+        for row in 0..report.len() {
+            let mut node = ROOT;
+            trie.nodes[node].count += 1;
+            for col in 0..report.width() {
+                let bit = report.bit(row, col) as usize;
+                if trie.nodes[node].children[bit] == NONE {
+                    trie.nodes[node].children[bit] = trie.nodes.len() as u32;
+                    trie.nodes.push(Node::default());
+                }
+                node = trie.nodes[node].children[bit] as usize;
+                trie.nodes[node].count += 1;
+            }
+        }
+
+        trie
+    }
+
+    fn child(&self, node: usize, bit: usize) -> Option<usize> {
+        match self.nodes[node].children[bit] {
+            NONE => None,
+            child => Some(child as usize),
+        }
+    }
+
+    fn count(&self, node: Option<usize>) -> usize {
+        node.map_or(0, |n| self.nodes[n].count)
+    }
+
+    pub fn count_prefix(&self, prefix: &str) -> usize {
+        let node = prefix.chars().try_fold(ROOT, |node, c| match c {
+            '0' => self.child(node, 0),
+            '1' => self.child(node, 1),
+            _ => panic!("Incorrect char"),
+        });
+        self.count(node)
+    }
+
+    // Walks from the root keeping the branch the criterion picks, falling
+    // through to the only branch when the other one is empty.
+    pub fn rating(&self, criterion: Criterion) -> String {
+        let mut path = String::with_capacity(self.width);
+        let mut node = ROOT;
+
+        for _ in 0..self.width {
+            let zero = self.child(node, 0);
+            let one = self.child(node, 1);
+            let ones = self.count(one);
+            let zeros = self.count(zero);
+            let bit = match (ones, zeros) {
+                (0, _) => 0,
+                (_, 0) => 1,
+                _ => criterion.keep_ones(ones, zeros) as usize,
+            };
+            path.push(if bit == 1 { '1' } else { '0' });
+            node = match self.child(node, bit) {
+                Some(n) => n,
+                None => panic!("Empty report"),
+            };
+        }
+
+        path
+    }
+
+    // Returns the k-th (0-based) most frequent row with its count, ties broken
+    // by lexicographic order. A subtree never holds a row more frequent than
+    // its own count, so rows leave the heap in rank order.
+    pub fn most_common(&self, k: usize) -> Option<(String, usize)> {
+        let mut heap = BinaryHeap::new();
+        if self.nodes[ROOT].count > 0 {
+            heap.push((self.nodes[ROOT].count, Reverse(String::new()), ROOT));
+        }
+
+        let mut seen = 0;
+        while let Some((count, Reverse(path), node)) = heap.pop() {
+            if path.len() == self.width {
+                if seen == k {
+                    return Some((path, count));
+                }
+                seen += 1;
+                continue;
+            }
+            for (bit, c) in [(0, '0'), (1, '1')] {
+                if let Some(child) = self.child(node, bit) {
+                    let mut child_path = path.clone();
+                    child_path.push(c);
+                    heap.push((self.nodes[child].count, Reverse(child_path), child));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::Tie;
+    use num_bigint::BigUint;
+
+    fn sample() -> Report {
+        let mut report = Report::new(5);
+        [
+            "00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000",
+            "11001", "00010", "01010", "10110",
+        ]
+        .iter()
+        .for_each(|l| report.push(l));
+        report
+    }
+
+    #[test]
+    fn test_ratings_match_report() {
+        let report = sample();
+        let trie = Trie::from_report(&report);
+
+        let oxy_criterion = Criterion::MostCommon(Tie::Ones);
+        let co2_criterion = Criterion::LeastCommon(Tie::Zeros);
+        let oxy = report.rating(oxy_criterion);
+        let co2 = report.rating(co2_criterion);
+        assert_eq!(
+            report.value(oxy),
+            BigUint::parse_bytes(trie.rating(oxy_criterion).as_bytes(), 2).unwrap()
+        );
+        assert_eq!(
+            report.value(co2),
+            BigUint::parse_bytes(trie.rating(co2_criterion).as_bytes(), 2).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_queries() {
+        let trie = Trie::from_report(&sample());
+
+        assert_eq!(13, trie.count_prefix(""));
+        assert_eq!(8, trie.count_prefix("1"));
+        assert_eq!(4, trie.count_prefix("101"));
+        assert_eq!(0, trie.count_prefix("0000"));
+        assert_eq!(Some(("10110".to_string(), 2)), trie.most_common(0));
+        assert_eq!(Some(("00010".to_string(), 1)), trie.most_common(1));
+        assert_eq!(None, trie.most_common(12));
+    }
+}
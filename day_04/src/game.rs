@@ -0,0 +1,104 @@
+use crate::index::Index;
+use crate::{BoardResult, Generated};
+
+#[derive(Debug, PartialEq)]
+pub struct Mark {
+    pub board: usize,
+    pub row: usize,
+    pub col: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Win {
+    pub board: usize,
+    pub score: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Event {
+    pub n: usize,
+    pub number: usize,
+    pub marked: Vec<Mark>,
+    pub won: Vec<Win>,
+}
+
+// Plays all boards at once, one draw per item. Boards keep being marked after
+// they have won, but each board is reported as a winner only once.
+pub struct Game<'a> {
+    numbers: &'a [usize],
+    index: Index<'a>,
+    finished: Vec<bool>,
+    n: usize,
+}
+
+impl Game<'_> {
+    pub fn new(input: &Generated) -> Game<'_> {
+        Game {
+            numbers: &input.numbers,
+            index: Index::new(input),
+            finished: vec![false; input.boards.len()],
+            n: 0,
+        }
+    }
+}
+
+impl Iterator for Game<'_> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.n;
+        let number = *self.numbers.get(n)?;
+        self.n += 1;
+
+        let (marked, completed) = self.index.draw(number);
+        let mut won = Vec::new();
+        for (board, sum) in completed {
+            if !self.finished[board] {
+                self.finished[board] = true;
+                won.push(Win {
+                    board,
+                    score: sum * number,
+                });
+            }
+        }
+
+        Some(Event {
+            n,
+            number,
+            marked,
+            won,
+        })
+    }
+}
+
+pub struct Standing {
+    pub board: usize,
+    pub result: Option<BoardResult>,
+}
+
+// Every board in finishing order, ties on the same draw by board index, with
+// the boards that never win at the end.
+pub fn ranking(input: &Generated) -> Vec<Standing> {
+    let mut standings = Vec::with_capacity(input.boards.len());
+    let mut placed = vec![false; input.boards.len()];
+
+    for event in Game::new(input) {
+        for Win { board, score } in event.won {
+            placed[board] = true;
+            standings.push(Standing {
+                board,
+                result: Some(BoardResult { n: event.n, score }),
+            });
+        }
+    }
+    standings.extend(
+        (0..input.boards.len())
+            .filter(|&board| !placed[board])
+            .map(|board| Standing {
+                board,
+                result: None,
+            }),
+    );
+
+    standings
+}
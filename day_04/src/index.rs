@@ -0,0 +1,134 @@
+use crate::game::Mark;
+use crate::{Generated, WinRule};
+use std::collections::HashMap;
+
+// Hit counters for every line a win rule can complete, so a draw only touches
+// the cells holding the drawn number instead of scanning every board.
+pub struct Index<'a> {
+    rules: &'a [WinRule],
+    positions: HashMap<usize, Vec<Mark>>,
+    shapes: Vec<(usize, usize)>,
+    cell_offsets: Vec<usize>,
+    row_offsets: Vec<usize>,
+    col_offsets: Vec<usize>,
+    marked: Vec<bool>,
+    row_hits: Vec<usize>,
+    col_hits: Vec<usize>,
+    diagonal_hits: Vec<[usize; 2]>,
+    corner_hits: Vec<usize>,
+    cell_hits: Vec<usize>,
+    sums: Vec<usize>,
+}
+
+impl Index<'_> {
+    pub fn new(input: &Generated) -> Index<'_> {
+        let mut index = Index {
+            rules: &input.rules,
+            positions: HashMap::new(),
+            shapes: Vec::with_capacity(input.boards.len()),
+            cell_offsets: Vec::with_capacity(input.boards.len()),
+            row_offsets: Vec::with_capacity(input.boards.len()),
+            col_offsets: Vec::with_capacity(input.boards.len()),
+            marked: Vec::new(),
+            row_hits: Vec::new(),
+            col_hits: Vec::new(),
+            diagonal_hits: vec![[0; 2]; input.boards.len()],
+            corner_hits: vec![0; input.boards.len()],
+            cell_hits: vec![0; input.boards.len()],
+            sums: Vec::with_capacity(input.boards.len()),
+        };
+
+        for (board, cells) in input.boards.iter().enumerate() {
+            let (rows, cols) = (cells.len(), cells[0].len());
+            index.shapes.push((rows, cols));
+            index.cell_offsets.push(index.marked.len());
+            index.row_offsets.push(index.row_hits.len());
+            index.col_offsets.push(index.col_hits.len());
+            index.marked.resize(index.marked.len() + rows * cols, false);
+            index.row_hits.resize(index.row_hits.len() + rows, 0);
+            index.col_hits.resize(index.col_hits.len() + cols, 0);
+            index.sums.push(cells.iter().flatten().sum());
+
+            for (row, line) in cells.iter().enumerate() {
+                for (col, &v) in line.iter().enumerate() {
+                    index
+                        .positions
+                        .entry(v)
+                        .or_default()
+                        .push(Mark { board, row, col });
+                }
+            }
+        }
+
+        index
+    }
+
+    // Marks every unmarked cell holding `number`. Returns the new marks and the
+    // boards that completed a winning line with them, both in board order. A
+    // board comes with its unmarked sum right after the first completing mark,
+    // as a number repeated on the board may be marked again after that.
+    pub fn draw(&mut self, number: usize) -> (Vec<Mark>, Vec<(usize, usize)>) {
+        let mut marks = Vec::new();
+        let mut completed: Vec<(usize, usize)> = Vec::new();
+
+        let positions = match self.positions.get(&number) {
+            Some(positions) => positions,
+            None => return (marks, completed),
+        };
+
+        for &Mark { board, row, col } in positions {
+            let (rows, cols) = self.shapes[board];
+            let cell = self.cell_offsets[board] + row * cols + col;
+            if self.marked[cell] {
+                continue;
+            }
+            self.marked[cell] = true;
+            self.sums[board] -= number;
+
+            let row_hits = &mut self.row_hits[self.row_offsets[board] + row];
+            *row_hits += 1;
+            let row_done = *row_hits == cols;
+            let col_hits = &mut self.col_hits[self.col_offsets[board] + col];
+            *col_hits += 1;
+            let col_done = *col_hits == rows;
+            let mut diagonal_done = false;
+            if rows == cols && row == col {
+                self.diagonal_hits[board][0] += 1;
+                diagonal_done |= self.diagonal_hits[board][0] == rows;
+            }
+            if rows == cols && row + col == cols - 1 {
+                self.diagonal_hits[board][1] += 1;
+                diagonal_done |= self.diagonal_hits[board][1] == rows;
+            }
+            let mut corners_done = false;
+            if (row == 0 || row == rows - 1) && (col == 0 || col == cols - 1) {
+                self.corner_hits[board] += 1;
+                corners_done = self.corner_hits[board] == corners(rows, cols);
+            }
+            self.cell_hits[board] += 1;
+            let full_done = self.cell_hits[board] == rows * cols;
+
+            let wins = self.rules.iter().any(|rule| match rule {
+                WinRule::Row => row_done,
+                WinRule::Column => col_done,
+                WinRule::Diagonal => diagonal_done,
+                WinRule::Corners => corners_done,
+                WinRule::FullCard => full_done,
+            });
+            if wins && completed.last().map(|&(b, _)| b) != Some(board) {
+                completed.push((board, self.sums[board]));
+            }
+            marks.push(Mark { board, row, col });
+        }
+
+        (marks, completed)
+    }
+}
+
+fn corners(rows: usize, cols: usize) -> usize {
+    match (rows, cols) {
+        (1, 1) => 1,
+        (1, _) | (_, 1) => 2,
+        _ => 4,
+    }
+}
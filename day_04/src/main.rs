@@ -1,13 +1,44 @@
+mod game;
+mod index;
+mod rigging;
+
+use game::{ranking, Game};
+use rigging::{rig, Goal};
+use std::env;
 use std::fs;
+use std::str::FromStr;
 use std::time::Instant;
 
-const BOARD_SIZE: usize = 5;
-
 type Board = Vec<Vec<usize>>;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum WinRule {
+    Row,
+    Column,
+    // Both corner to corner diagonals, only possible on square boards.
+    Diagonal,
+    Corners,
+    FullCard,
+}
+
+impl FromStr for WinRule {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "row" => Ok(WinRule::Row),
+            "column" => Ok(WinRule::Column),
+            "diagonal" => Ok(WinRule::Diagonal),
+            "corners" => Ok(WinRule::Corners),
+            "full" => Ok(WinRule::FullCard),
+            _ => Err(format!("Unknown win rule: {}", s)),
+        }
+    }
+}
+
 struct Generated {
     numbers: Vec<usize>,
     boards: Vec<Board>,
+    rules: Vec<WinRule>,
 }
 
 fn generate(input: &str) -> Generated {
@@ -16,42 +47,68 @@ fn generate(input: &str) -> Generated {
     let numbers = input.next().unwrap();
 
     let numbers = numbers
-        .split(",")
+        .trim()
+        .split(',')
         .map(|v| v.parse().unwrap())
         .collect::<Vec<usize>>();
 
     let boards = input
         .map(|b| {
-            b.split('\n')
+            b.lines()
+                .filter(|line| !line.trim().is_empty())
                 .map(|line| {
-                    line.trim()
-                        .split_whitespace()
+                    line.split_whitespace()
                         .map(|v| v.parse::<usize>().unwrap())
                         .collect::<Vec<usize>>()
                 })
                 .collect::<Board>()
         })
+        .filter(|b| !b.is_empty())
         .collect::<Vec<Board>>();
 
-    Generated { numbers, boards }
+    if boards
+        .iter()
+        .any(|b| b[0].is_empty() || b.iter().any(|row| row.len() != b[0].len()))
+    {
+        panic!("Board is not rectangular");
+    }
+
+    Generated {
+        numbers,
+        boards,
+        rules: vec![WinRule::Row, WinRule::Column],
+    }
 }
 
-fn board_wins(board: &[[bool; BOARD_SIZE]; BOARD_SIZE]) -> bool {
-    let full_row = board.iter().any(|r| r == &[true; BOARD_SIZE]);
-    let full_col = (0..5).any(|col| {
-        board[0][col] && board[1][col] && board[2][col] && board[3][col] && board[4][col]
-    });
-    full_row || full_col
+#[cfg(test)]
+fn board_wins(hits: &[Vec<bool>], rules: &[WinRule]) -> bool {
+    let rows = hits.len();
+    let cols = hits[0].len();
+    rules.iter().any(|rule| match rule {
+        WinRule::Row => hits.iter().any(|r| r.iter().all(|&h| h)),
+        WinRule::Column => (0..cols).any(|col| hits.iter().all(|r| r[col])),
+        WinRule::Diagonal => {
+            rows == cols
+                && ((0..rows).all(|i| hits[i][i]) || (0..rows).all(|i| hits[i][cols - 1 - i]))
+        }
+        WinRule::Corners => {
+            hits[0][0] && hits[0][cols - 1] && hits[rows - 1][0] && hits[rows - 1][cols - 1]
+        }
+        WinRule::FullCard => hits.iter().flatten().all(|&h| h),
+    })
 }
 
+#[derive(Debug)]
 struct BoardResult {
     n: usize,
     score: usize,
 }
 
-fn get_score(numbers: &Vec<usize>, board: &Board) -> Option<BoardResult> {
+// Scans each board on its own; kept as the reference for the indexed `Game`.
+#[cfg(test)]
+fn get_score(numbers: &[usize], board: &Board, rules: &[WinRule]) -> Option<BoardResult> {
     let mut sum = board.iter().flatten().sum::<usize>();
-    let mut hit_board = [[false; BOARD_SIZE]; BOARD_SIZE];
+    let mut hit_board = vec![vec![false; board[0].len()]; board.len()];
 
     for (n, number) in numbers.iter().enumerate() {
         for (i, row) in board.iter().enumerate() {
@@ -59,7 +116,7 @@ fn get_score(numbers: &Vec<usize>, board: &Board) -> Option<BoardResult> {
                 if number == v {
                     hit_board[i][j] = true;
                     sum -= number;
-                    if board_wins(&hit_board) {
+                    if board_wins(&hit_board, rules) {
                         return Some(BoardResult {
                             n,
                             score: sum * number,
@@ -74,29 +131,37 @@ fn get_score(numbers: &Vec<usize>, board: &Board) -> Option<BoardResult> {
 }
 
 fn part_1(input: &Generated) -> usize {
-    let results: Vec<BoardResult> = input
-        .boards
+    ranking(input)
         .iter()
-        .map(|b| get_score(&input.numbers, b).expect("No result"))
-        .collect();
-
-    results.iter().min_by(|a, b| a.n.cmp(&b.n)).unwrap().score
+        .find_map(|s| s.result.as_ref())
+        .expect("No board wins")
+        .score
 }
 
 fn part_2(input: &Generated) -> usize {
-    let results: Vec<BoardResult> = input
-        .boards
+    ranking(input)
         .iter()
-        .map(|b| get_score(&input.numbers, b).expect("No result"))
-        .collect();
-
-    results.iter().max_by(|a, b| a.n.cmp(&b.n)).unwrap().score
+        .rev()
+        .find_map(|s| s.result.as_ref())
+        .expect("No board wins")
+        .score
 }
 
 fn main() {
     let content = fs::read_to_string("input").expect("file not found");
 
-    let data = generate(&content);
+    let mut data = generate(&content);
+
+    // e.g. `--rules row,column,diagonal`
+    let args = env::args().collect::<Vec<String>>();
+    if let Some(pos) = args.iter().position(|a| a == "--rules") {
+        data.rules = args
+            .get(pos + 1)
+            .expect("No rules given")
+            .split(',')
+            .map(|r| WinRule::from_str(r).unwrap())
+            .collect();
+    }
 
     let res1_start = Instant::now();
     let res1 = part_1(&data);
@@ -116,11 +181,59 @@ fn main() {
         res2,
         res2_stop.duration_since(res2_start)
     );
+
+    if args.iter().any(|a| a == "--events") {
+        for event in Game::new(&data) {
+            println!(
+                "Draw {}: {} marked {} cells, won: {:?}",
+                event.n,
+                event.number,
+                event.marked.len(),
+                event.won
+            );
+        }
+    }
+    // e.g. `--rig 3 last 20`
+    if let Some(pos) = args.iter().position(|a| a == "--rig") {
+        let target = args[pos + 1].parse().expect("Incorrect board");
+        let goal = match args[pos + 2].as_str() {
+            "first" => Goal::First,
+            "last" => Goal::Last,
+            _ => panic!("Incorrect goal"),
+        };
+        let max_len = args[pos + 3].parse().expect("Incorrect length");
+        match rig(&data, target, goal, max_len) {
+            Some(sequence) => println!(
+                "{}",
+                sequence
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ),
+            None => println!("No sequence of at most {} draws", max_len),
+        }
+    }
+    if args.iter().any(|a| a == "--ranking") {
+        for (place, standing) in ranking(&data).iter().enumerate() {
+            match &standing.result {
+                Some(r) => println!(
+                    "{}. board {} won on draw {} with score {}",
+                    place + 1,
+                    standing.board,
+                    r.n,
+                    r.score
+                ),
+                None => println!("{}. board {} never won", place + 1, standing.board),
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use game::{Mark, Win};
     #[test]
     fn test_part_1() {
         assert_eq!(
@@ -175,4 +288,138 @@ mod tests {
             ))
         )
     }
+    #[test]
+    fn test_win_rules() {
+        let mut game = generate("5,1,9,3,7\n\n1 2 3\n4 5 6\n7 8 9\n\n1 2 3 4\n5 6 7 8\n");
+        assert_eq!((2, 4), (game.boards[1].len(), game.boards[1][0].len()));
+        assert!(get_score(&game.numbers, &game.boards[0], &game.rules).is_none());
+
+        game.rules = vec![WinRule::Diagonal];
+        let result = get_score(&game.numbers, &game.boards[0], &game.rules).unwrap();
+        assert_eq!((2, 30 * 9), (result.n, result.score));
+        assert!(get_score(&game.numbers, &game.boards[1], &game.rules).is_none());
+
+        game.rules = vec![WinRule::Corners];
+        let result = get_score(&game.numbers, &game.boards[0], &game.rules).unwrap();
+        assert_eq!((4, 20 * 7), (result.n, result.score));
+    }
+    #[test]
+    #[should_panic(expected = "Board is not rectangular")]
+    fn test_ragged_board() {
+        generate("1,2\n\n1 2\n3\n");
+    }
+    #[test]
+    fn test_events_and_ranking() {
+        let game = generate("4,2,1,3\n\n1 2\n3 4\n\n4 5\n6 7\n\n2 4\n9 9\n");
+        let events = Game::new(&game).collect::<Vec<_>>();
+        assert_eq!(4, events.len());
+        assert_eq!(
+            vec![
+                Mark {
+                    board: 0,
+                    row: 1,
+                    col: 1
+                },
+                Mark {
+                    board: 1,
+                    row: 0,
+                    col: 0
+                },
+                Mark {
+                    board: 2,
+                    row: 0,
+                    col: 1
+                }
+            ],
+            events[0].marked
+        );
+        assert_eq!(
+            vec![
+                Win {
+                    board: 0,
+                    score: 4 * 2
+                },
+                Win {
+                    board: 2,
+                    score: 18 * 2
+                }
+            ],
+            events[1].won
+        );
+        assert!(events[2].won.is_empty());
+
+        let standings = ranking(&game);
+        assert_eq!(
+            vec![0, 2, 1],
+            standings.iter().map(|s| s.board).collect::<Vec<_>>()
+        );
+        assert_eq!(1, standings[1].result.as_ref().unwrap().n);
+        assert!(standings[2].result.is_none());
+        assert_eq!(36, part_2(&game));
+    }
+    #[test]
+    fn test_repeated_number() {
+        // The board wins on the first 1, before the second one is marked.
+        let game = generate("2,1\n\n1 2\n3 1\n");
+        let scan = get_score(&game.numbers, &game.boards[0], &game.rules).unwrap();
+        assert_eq!(4, scan.score);
+        assert_eq!(4, part_1(&game));
+    }
+    #[test]
+    fn test_index_matches_scan() {
+        let mut seed = 7u64;
+        let mut next = |m: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % m) as usize
+        };
+        let mut shuffled = |count: usize| {
+            let mut values = (0..50).collect::<Vec<usize>>();
+            (0..count)
+                .map(|i| {
+                    let j = i + next((50 - i) as u64);
+                    values.swap(i, j);
+                    values[i]
+                })
+                .collect::<Vec<usize>>()
+        };
+        let numbers = shuffled(40);
+        let boards = (0..50)
+            .map(|b| {
+                let (rows, cols) = (1 + b % 5, 1 + b * 3 / 7 % 5);
+                let mut values = shuffled(rows * cols);
+                // Repeat some values within the board.
+                for i in 1..values.len() {
+                    if values[i] % 4 == 0 {
+                        values[i] = values[i - 1];
+                    }
+                }
+                values.chunks(cols).map(|row| row.to_vec()).collect()
+            })
+            .collect::<Vec<Board>>();
+        let rule_sets = [
+            vec![WinRule::Row, WinRule::Column],
+            vec![WinRule::Diagonal],
+            vec![WinRule::Corners, WinRule::Row],
+            vec![WinRule::FullCard],
+        ];
+
+        for rules in rule_sets {
+            let game = Generated {
+                numbers: numbers.clone(),
+                boards: boards.clone(),
+                rules,
+            };
+            let standings = ranking(&game);
+            for standing in standings {
+                let board = &game.boards[standing.board];
+                let scan = get_score(&game.numbers, board, &game.rules);
+                assert_eq!(
+                    scan.map(|r| (r.n, r.score)),
+                    standing.result.map(|r| (r.n, r.score))
+                );
+            }
+        }
+    }
 }
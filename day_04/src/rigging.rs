@@ -0,0 +1,277 @@
+use crate::{Board, Generated, WinRule};
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Goal {
+    First,
+    Last,
+}
+
+// The sets of numbers that win a board under the given rules.
+fn lines(board: &Board, rules: &[WinRule]) -> Vec<Vec<usize>> {
+    let rows = board.len();
+    let cols = board[0].len();
+    let mut lines: Vec<Vec<usize>> = Vec::new();
+
+    for rule in rules {
+        match rule {
+            WinRule::Row => lines.extend(board.iter().cloned()),
+            WinRule::Column => {
+                lines.extend((0..cols).map(|col| board.iter().map(|r| r[col]).collect()))
+            }
+            WinRule::Diagonal if rows == cols => {
+                lines.push((0..rows).map(|i| board[i][i]).collect());
+                lines.push((0..rows).map(|i| board[i][cols - 1 - i]).collect());
+            }
+            WinRule::Diagonal => (),
+            WinRule::Corners => {
+                let mut corners = vec![
+                    board[0][0],
+                    board[0][cols - 1],
+                    board[rows - 1][0],
+                    board[rows - 1][cols - 1],
+                ];
+                corners.sort_unstable();
+                corners.dedup();
+                lines.push(corners);
+            }
+            WinRule::FullCard => lines.push(board.iter().flatten().cloned().collect()),
+        }
+    }
+
+    lines.iter_mut().for_each(|l| {
+        l.sort_unstable();
+        l.dedup();
+    });
+    lines.sort_by_key(|l| l.len());
+    lines
+}
+
+struct Rigging {
+    lines: Vec<Vec<Vec<usize>>>,
+    target: usize,
+    max_len: usize,
+    best: Option<Vec<usize>>,
+    seen: HashSet<Vec<usize>>,
+}
+
+impl Rigging {
+    fn complete(&self, board: usize, drawn: &HashSet<usize>, extra: Option<usize>) -> bool {
+        self.lines[board]
+            .iter()
+            .any(|l| l.iter().all(|v| drawn.contains(v) || Some(*v) == extra))
+    }
+
+    fn limit(&self) -> usize {
+        self.best.as_ref().map_or(self.max_len, |b| b.len() - 1)
+    }
+
+    // Boards after the target may not share its final draw, so their lines
+    // have to be finished without `last`.
+    fn finished(&self, board: usize, drawn: &HashSet<usize>, last: usize) -> bool {
+        match board < self.target {
+            true => self.complete(board, drawn, Some(last)),
+            false => self.complete(board, drawn, None),
+        }
+    }
+
+    fn missing(&self, board: usize, drawn: &HashSet<usize>, last: usize) -> usize {
+        self.lines[board]
+            .iter()
+            .filter(|l| board < self.target || !l.contains(&last))
+            .map(|l| {
+                l.iter()
+                    .filter(|&&v| !drawn.contains(&v) && (v != last))
+                    .count()
+            })
+            .min()
+            .unwrap_or(usize::MAX / 2)
+    }
+
+    fn record(&mut self, drawn: &HashSet<usize>, last: usize) {
+        let mut sequence = drawn.iter().cloned().collect::<Vec<usize>>();
+        sequence.sort_unstable();
+        sequence.push(last);
+        self.best = Some(sequence);
+    }
+
+    // Every other board must be finished by the draw of `last`, while the
+    // target stays open until then.
+    fn search_last(&mut self, drawn: &mut HashSet<usize>, last: usize) {
+        if self.complete(self.target, drawn, None) {
+            return;
+        }
+        // The same set is reached once per order its lines were picked in.
+        let mut key = drawn.iter().cloned().collect::<Vec<usize>>();
+        key.sort_unstable();
+        key.push(last);
+        if !self.seen.insert(key) {
+            return;
+        }
+
+        // Branch on the open board furthest from finishing, it constrains the
+        // bound the most.
+        let open = (0..self.lines.len())
+            .filter(|&b| b != self.target && !self.finished(b, drawn, last))
+            .map(|b| (self.missing(b, drawn, last), b))
+            .max_by_key(|&(missing, b)| (missing, usize::MAX - b));
+        let (missing, board) = open.unwrap_or((0, usize::MAX));
+        if drawn.len() + 1 + missing > self.limit() {
+            return;
+        }
+        if board == usize::MAX {
+            return self.record(drawn, last);
+        }
+
+        for l in self.lines[board].clone() {
+            if board > self.target && l.contains(&last) {
+                continue;
+            }
+            let added = l
+                .into_iter()
+                .filter(|&v| v != last && drawn.insert(v))
+                .collect::<Vec<usize>>();
+            self.search_last(drawn, last);
+            added.iter().for_each(|v| {
+                drawn.remove(v);
+            });
+        }
+    }
+}
+
+// Finds a shortest sequence of distinct numbers after which the target board
+// is the first (or last) entry of `ranking`, or proves there is none of at
+// most `max_len` draws. Drawing numbers off the target only helps the other
+// boards, so for `First` the answer is one of the target's own lines; `Last`
+// is a branch and bound over which line finishes each remaining board, and
+// grows exponentially with the number of boards.
+pub fn rig(input: &Generated, target: usize, goal: Goal, max_len: usize) -> Option<Vec<usize>> {
+    let mut rigging = Rigging {
+        lines: input
+            .boards
+            .iter()
+            .map(|b| lines(b, &input.rules))
+            .collect(),
+        target,
+        max_len,
+        best: None,
+        seen: HashSet::new(),
+    };
+
+    for line in rigging.lines[target].clone() {
+        for &last in &line {
+            let mut drawn = line
+                .iter()
+                .filter(|&&v| v != last)
+                .cloned()
+                .collect::<HashSet<usize>>();
+            if drawn.len() + 1 > rigging.limit() {
+                continue;
+            }
+            match goal {
+                Goal::First => {
+                    let beaten = (0..rigging.lines.len()).any(|b| {
+                        rigging.complete(b, &drawn, None)
+                            || (b < target && rigging.complete(b, &drawn, Some(last)))
+                    });
+                    if !beaten {
+                        rigging.record(&drawn, last);
+                    }
+                }
+                Goal::Last => rigging.search_last(&mut drawn, last),
+            }
+        }
+    }
+
+    rigging.best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::ranking;
+
+    fn play(input: &Generated, numbers: Vec<usize>) -> Vec<Option<usize>> {
+        let game = Generated {
+            numbers,
+            boards: input.boards.clone(),
+            rules: input.rules.clone(),
+        };
+        ranking(&game)
+            .iter()
+            .map(|s| s.result.as_ref().map(|_| s.board))
+            .collect()
+    }
+
+    // Shortest length found by trying every sequence of distinct numbers.
+    fn brute_force(input: &Generated, target: usize, goal: Goal, max_len: usize) -> Option<usize> {
+        let mut numbers = input
+            .boards
+            .iter()
+            .flatten()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+        numbers.sort_unstable();
+        numbers.dedup();
+        let mut frontier: Vec<Vec<usize>> = vec![vec![]];
+        for len in 1..=max_len {
+            frontier = frontier
+                .iter()
+                .flat_map(|s| {
+                    numbers
+                        .iter()
+                        .filter(move |n| !s.contains(n))
+                        .map(move |&n| {
+                            let mut next = s.clone();
+                            next.push(n);
+                            next
+                        })
+                })
+                .collect();
+            let found = frontier.iter().any(|s| {
+                let order = play(input, s.clone());
+                match goal {
+                    Goal::First => order[0] == Some(target),
+                    Goal::Last => {
+                        order.iter().all(|b| b.is_some()) && order.last() == Some(&Some(target))
+                    }
+                }
+            });
+            if found {
+                return Some(len);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_rig_is_playable_and_shortest() {
+        let input = Generated {
+            numbers: vec![],
+            boards: vec![
+                vec![vec![1, 2], vec![3, 4]],
+                vec![vec![2, 5], vec![6, 1]],
+                vec![vec![4, 6], vec![5, 7]],
+            ],
+            rules: vec![WinRule::Row, WinRule::Column, WinRule::Diagonal],
+        };
+
+        for target in 0..3 {
+            for goal in [Goal::First, Goal::Last] {
+                let sequence = rig(&input, target, goal, 7);
+                assert_eq!(
+                    brute_force(&input, target, goal, 5),
+                    sequence.as_ref().map(|s| s.len())
+                );
+                let order = play(&input, sequence.unwrap());
+                match goal {
+                    Goal::First => assert_eq!(Some(target), order[0]),
+                    Goal::Last => assert_eq!(Some(target), *order.last().unwrap()),
+                }
+            }
+        }
+
+        assert_eq!(None, rig(&input, 0, Goal::Last, 2));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{Translation, TranslationWalker};
+use std::collections::HashMap;
+
+// Live coverage of the lattice points under a changing set of lines.
+// `levels[k]` is the number of points covered at least `k` times, kept up to
+// date on every insert and remove so any threshold is answered directly.
+pub struct Coverage {
+    lines: HashMap<usize, Translation>,
+    cells: HashMap<(i32, i32), usize>,
+    levels: Vec<usize>,
+    next_id: usize,
+}
+
+impl Coverage {
+    pub fn new() -> Coverage {
+        Coverage {
+            lines: HashMap::new(),
+            cells: HashMap::new(),
+            levels: vec![0],
+            next_id: 0,
+        }
+    }
+
+    pub fn insert(&mut self, line: Translation) -> usize {
+        for p in TranslationWalker::from_translation(&line) {
+            let count = self.cells.entry(p).or_insert(0);
+            *count += 1;
+            if self.levels.len() <= *count {
+                self.levels.push(0);
+            }
+            self.levels[*count] += 1;
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.lines.insert(id, line);
+        id
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<Translation> {
+        let line = self.lines.remove(&id)?;
+
+        for p in TranslationWalker::from_translation(&line) {
+            let count = self.cells.get_mut(&p).expect("Line not covered");
+            self.levels[*count] -= 1;
+            *count -= 1;
+            if *count == 0 {
+                self.cells.remove(&p);
+            }
+        }
+
+        Some(line)
+    }
+
+    pub fn at_least(&self, k: usize) -> usize {
+        match k {
+            0 => self.cells.len(),
+            k => self.levels.get(k).cloned().unwrap_or(0),
+        }
+    }
+
+    // How many points would drop below `k` if the line were removed.
+    pub fn removal_gain(&self, id: usize, k: usize) -> usize {
+        self.lines.get(&id).map_or(0, |line| {
+            TranslationWalker::from_translation(line)
+                .filter(|p| self.cells[p] == k)
+                .count()
+        })
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.lines.keys().cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    fn check(coverage: &Coverage, lines: &[&Translation]) {
+        let mut cells: HashMap<(i32, i32), usize> = HashMap::new();
+        lines
+            .iter()
+            .flat_map(|l| TranslationWalker::from_translation(l))
+            .for_each(|p| *cells.entry(p).or_insert(0) += 1);
+        for k in 0..5 {
+            let expected = cells.values().filter(|&&v| v >= k).count();
+            assert_eq!(expected, coverage.at_least(k));
+        }
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let lines = generate(
+            r"0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2",
+        );
+        let mut coverage = Coverage::new();
+        let ids = lines
+            .iter()
+            .map(|l| coverage.insert(l.clone()))
+            .collect::<Vec<usize>>();
+        assert_eq!(12, coverage.at_least(2));
+        check(&coverage, &lines.iter().collect::<Vec<_>>());
+
+        let gain = coverage.removal_gain(ids[8], 2);
+        assert_eq!(lines[8], coverage.remove(ids[8]).unwrap());
+        assert_eq!(12 - gain, coverage.at_least(2));
+        assert!(coverage.remove(ids[8]).is_none());
+        let mut rest = lines.iter().filter(|&l| l != &lines[8]).collect::<Vec<_>>();
+        check(&coverage, &rest);
+
+        coverage.insert(lines[0].clone());
+        coverage.insert(lines[0].clone());
+        coverage.remove(ids[3]);
+        rest.extend([&lines[0], &lines[0]]);
+        rest.retain(|&l| l != &lines[3]);
+        check(&coverage, &rest);
+    }
+}
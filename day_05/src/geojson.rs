@@ -0,0 +1,145 @@
+use crate::{Generated, Translation, TranslationWalker};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+fn point(value: &Value) -> Result<(i32, i32), String> {
+    let coords = value
+        .as_array()
+        .filter(|c| c.len() >= 2)
+        .ok_or(format!("Incorrect position: {}", value))?;
+    let coord = |v: &Value| {
+        v.as_i64()
+            .or_else(|| v.as_f64().filter(|f| f.fract() == 0.0).map(|f| f as i64))
+            .and_then(|i| i32::try_from(i).ok())
+            .ok_or(format!("Position is not on the integer grid: {}", value))
+    };
+    Ok((coord(&coords[0])?, coord(&coords[1])?))
+}
+
+// Reads every `LineString` (and `MultiLineString`) feature of a collection,
+// turning each pair of consecutive positions into one vent line.
+pub fn import(input: &str) -> Result<Generated, String> {
+    let root: Value = serde_json::from_str(input).map_err(|e| e.to_string())?;
+    let features = match root["type"].as_str() {
+        Some("FeatureCollection") => root["features"]
+            .as_array()
+            .ok_or("Missing features")?
+            .iter()
+            .collect::<Vec<&Value>>(),
+        Some("Feature") => vec![&root],
+        _ => return Err("Expected a FeatureCollection or a Feature".to_string()),
+    };
+
+    let mut lines = Vec::new();
+    for feature in features {
+        let geometry = &feature["geometry"];
+        let strings = match geometry["type"].as_str() {
+            Some("LineString") => vec![&geometry["coordinates"]],
+            Some("MultiLineString") => geometry["coordinates"]
+                .as_array()
+                .ok_or("Missing coordinates")?
+                .iter()
+                .collect(),
+            _ => continue,
+        };
+        for string in strings {
+            let points = string
+                .as_array()
+                .ok_or("Missing coordinates")?
+                .iter()
+                .map(point)
+                .collect::<Result<Vec<(i32, i32)>, String>>()?;
+            lines.extend(points.windows(2).map(|w| Translation {
+                from: w[0],
+                to: w[1],
+            }));
+        }
+    }
+
+    Ok(lines)
+}
+
+pub fn export_lines(input: &Generated) -> Value {
+    let features = input
+        .iter()
+        .enumerate()
+        .map(|(id, line)| {
+            json!({
+                "type": "Feature",
+                "properties": { "id": id },
+                "geometry": {
+                    "type": "LineString",
+                    "coordinates": [[line.from.0, line.from.1], [line.to.0, line.to.1]],
+                },
+            })
+        })
+        .collect::<Vec<Value>>();
+
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+// A single `MultiPoint` feature with the overlapping points, and their
+// coverage in a `coverage` property listed in the same order.
+pub fn export_overlaps(input: &Generated) -> Value {
+    let mut map: HashMap<(i32, i32), usize> = HashMap::new();
+    input
+        .iter()
+        .flat_map(TranslationWalker::from_translation)
+        .for_each(|p| *map.entry(p).or_insert(0) += 1);
+
+    let mut points = map
+        .into_iter()
+        .filter(|&(_, v)| v > 1)
+        .collect::<Vec<((i32, i32), usize)>>();
+    points.sort_unstable();
+
+    json!({
+        "type": "FeatureCollection",
+        "features": [{
+            "type": "Feature",
+            "properties": {
+                "coverage": points.iter().map(|(_, v)| *v).collect::<Vec<usize>>(),
+            },
+            "geometry": {
+                "type": "MultiPoint",
+                "coordinates": points.iter().map(|(p, _)| [p.0, p.1]).collect::<Vec<[i32; 2]>>(),
+            },
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+
+    #[test]
+    fn test_round_trip() {
+        let lines = generate("0,9 -> 5,9\n8,0 -> 0,8\n7,0 -> 7,4\n0,1 -> 9,1\n2,9 -> 2,0\n");
+        let exported = export_lines(&lines).to_string();
+        assert_eq!(lines, import(&exported).unwrap());
+
+        let overlaps = export_overlaps(&lines);
+        let feature = &overlaps["features"][0];
+        assert_eq!(
+            json!([[2, 1], [2, 6], [2, 9], [7, 1]]),
+            feature["geometry"]["coordinates"]
+        );
+        assert_eq!(json!([2, 2, 2, 3]), feature["properties"]["coverage"]);
+    }
+
+    #[test]
+    fn test_import() {
+        let input = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "LineString", "coordinates": [[0, 0], [2.0, 2], [2, 5]]}},
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}}
+        ]}"#;
+        assert_eq!(generate("0,0 -> 2,2\n2,2 -> 2,5"), import(input).unwrap());
+
+        let input = r#"{"type": "Feature", "properties": {},
+            "geometry": {"type": "LineString", "coordinates": [[0, 0.5], [2, 2]]}}"#;
+        assert!(import(input).is_err());
+    }
+}
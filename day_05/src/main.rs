@@ -1,4 +1,12 @@
+mod coverage;
+mod geojson;
+mod query;
+mod sweep;
+
+use coverage::Coverage;
+use query::Hazards;
 use std::collections::HashMap;
+use std::env;
 use std::fs;
 use std::num::ParseIntError;
 use std::str::FromStr;
@@ -6,7 +14,7 @@ use std::time::Instant;
 
 type Generated = Vec<Translation>;
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 struct Translation {
     from: (i32, i32),
     to: (i32, i32),
@@ -32,22 +40,49 @@ impl FromStr for Translation {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Raster {
+    // Only the points the segment passes through exactly.
+    Lattice,
+    // Every cell the segment crosses, one per step along the major axis.
+    Bresenham,
+}
+
 struct TranslationWalker<'a> {
     translation: &'a Translation,
+    raster: Raster,
     position: (i32, i32),
-    step: (i32, i32),
+    step: (i64, i64),
+    delta: (i64, i64),
+    error: i64,
     exhausted: bool,
 }
 
 impl TranslationWalker<'_> {
-    fn from_translation(translation: &Translation) -> TranslationWalker {
+    fn from_translation(translation: &Translation) -> TranslationWalker<'_> {
+        TranslationWalker::new(translation, Raster::Lattice)
+    }
+
+    fn new(translation: &Translation, raster: Raster) -> TranslationWalker<'_> {
+        // Wide enough for lines spanning the whole i32 range.
+        let dx = translation.to.0 as i64 - translation.from.0 as i64;
+        let dy = translation.to.1 as i64 - translation.from.1 as i64;
+        let step = match raster {
+            Raster::Lattice => match sweep::gcd(dx, dy) {
+                0 => (0, 0),
+                g => (dx / g, dy / g),
+            },
+            Raster::Bresenham => (dx.signum(), dy.signum()),
+        };
+        let delta = (dx.abs(), -dy.abs());
+
         TranslationWalker {
             translation,
+            raster,
             position: translation.from,
-            step: (
-                i32::signum(translation.to.0 - translation.from.0),
-                i32::signum(translation.to.1 - translation.from.1),
-            ),
+            step,
+            delta,
+            error: delta.0 + delta.1,
             exhausted: false,
         }
     }
@@ -65,8 +100,23 @@ impl Iterator for TranslationWalker<'_> {
         } else {
             let res = Some(self.position);
 
-            self.position.0 += self.step.0;
-            self.position.1 += self.step.1;
+            match self.raster {
+                Raster::Lattice => {
+                    self.position.0 = (self.position.0 as i64 + self.step.0) as i32;
+                    self.position.1 = (self.position.1 as i64 + self.step.1) as i32;
+                }
+                Raster::Bresenham => {
+                    let e2 = 2 * self.error;
+                    if e2 >= self.delta.1 {
+                        self.error += self.delta.1;
+                        self.position.0 += self.step.0 as i32;
+                    }
+                    if e2 <= self.delta.0 {
+                        self.error += self.delta.0;
+                        self.position.1 += self.step.1 as i32;
+                    }
+                }
+            }
 
             res
         }
@@ -83,31 +133,21 @@ fn generate(input: &str) -> Generated {
 }
 
 fn part_1(input: &Generated) -> usize {
-    let only_strait: Vec<&Translation> = input
+    let only_strait = input
         .iter()
         .filter(|i| i.from.0 == i.to.0 || i.from.1 == i.to.1)
-        .collect();
+        .cloned()
+        .collect::<Vec<Translation>>();
 
-    let mut map: HashMap<(i32, i32), usize> = HashMap::new();
-
-    only_strait
-        .iter()
-        .map(|t| TranslationWalker::from_translation(t))
-        .for_each(|walker| {
-            walker.into_iter().for_each(|step| {
-                *map.entry(step).or_insert(0) += 1
-            })
-        });
-
-    map.iter().filter(|(_, &v)| v > 1).count()
+    sweep::count_overlaps(&only_strait)
 }
 
-fn part_2(input: &Generated) -> usize {
+fn overlaps(input: &Generated, raster: Raster) -> usize {
     let mut map: HashMap<(i32, i32), usize> = HashMap::new();
 
     input
         .iter()
-        .map(TranslationWalker::from_translation)
+        .map(|t| TranslationWalker::new(t, raster))
         .for_each(|walker| {
             walker.into_iter().for_each(|step| {
                 let val = map.entry(step).or_insert(0);
@@ -118,10 +158,26 @@ fn part_2(input: &Generated) -> usize {
     map.iter().filter(|(_, &v)| v > 1).count()
 }
 
+fn part_2(input: &Generated) -> usize {
+    sweep::count_overlaps(input)
+}
+
 fn main() {
-    let content = fs::read_to_string("input").expect("file not found");
+    let args = env::args().collect::<Vec<String>>();
+    let arg = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .map(|pos| args.get(pos + 1).expect("Missing argument value").as_str())
+    };
 
-    let data = generate(&content);
+    // e.g. `--import vents.geojson`
+    let data = match arg("--import") {
+        Some(path) => {
+            let content = fs::read_to_string(path).expect("file not found");
+            geojson::import(&content).unwrap()
+        }
+        None => generate(&fs::read_to_string("input").expect("file not found")),
+    };
 
     let res1_start = Instant::now();
     let res1 = part_1(&data);
@@ -141,6 +197,65 @@ fn main() {
         res2,
         res2_stop.duration_since(res2_start)
     );
+
+    if args.iter().any(|a| a == "--bresenham") {
+        println!("Bresenham overlaps: {}", overlaps(&data, Raster::Bresenham));
+    }
+
+    if args.iter().any(|a| a == "--what-if") {
+        let mut coverage = Coverage::new();
+        data.iter().for_each(|l| {
+            coverage.insert(l.clone());
+        });
+        let mut gains = coverage
+            .ids()
+            .map(|id| (coverage.removal_gain(id, 2), id))
+            .collect::<Vec<(usize, usize)>>();
+        gains.sort_unstable_by(|a, b| b.cmp(a));
+        for &(gain, id) in gains.iter().take(5) {
+            let line = coverage.remove(id).unwrap();
+            println!(
+                "Without {:?}: {} overlaps ({} fewer)",
+                line,
+                coverage.at_least(2),
+                gain
+            );
+            coverage.insert(line);
+        }
+    }
+
+    if let Some(path) = arg("--export-lines") {
+        fs::write(path, geojson::export_lines(&data).to_string()).expect("write failed");
+    }
+    if let Some(path) = arg("--export-overlaps") {
+        fs::write(path, geojson::export_overlaps(&data).to_string()).expect("write failed");
+    }
+
+    // e.g. `--at 5,9`, `--crossing "0,0 -> 9,9"`, `--rect "0,0 -> 9,9"`
+    let hazards = Hazards::new(&data);
+    if let Some(at) = arg("--at") {
+        let p = at
+            .split(',')
+            .map(|v| v.trim().parse::<i32>().unwrap())
+            .collect::<Vec<i32>>();
+        println!("Lines at {}: {:?}", at, hazards.covering((p[0], p[1])));
+    }
+    if let Some(line) = arg("--crossing") {
+        let line = Translation::from_str(line).unwrap();
+        println!(
+            "Lines crossing {:?}: {:?}",
+            line,
+            hazards.intersecting(&line)
+        );
+    }
+    if let Some(rect) = arg("--rect") {
+        let rect = Translation::from_str(rect).unwrap();
+        println!(
+            "Overlaps in {:?}: {}",
+            rect,
+            hazards.overlaps_in(rect.from, rect.to)
+        );
+    }
 }
 
 #[cfg(test)]
@@ -148,6 +263,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::useless_conversion)]
     fn test_iter_for_translation() {
         let mut iter = TranslationWalker::from_translation(&Translation {
             from: (0, 0),
@@ -172,6 +288,48 @@ mod tests {
         assert_eq!(None, iter.next());
     }
 
+    #[test]
+    fn test_iter_for_any_slope() {
+        let line = Translation {
+            from: (0, 0),
+            to: (6, -4),
+        };
+        assert_eq!(
+            vec![(0, 0), (3, -2), (6, -4)],
+            TranslationWalker::new(&line, Raster::Lattice).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![(0, 0), (1, -1), (2, -1), (3, -2), (4, -3), (5, -3), (6, -4)],
+            TranslationWalker::new(&line, Raster::Bresenham).collect::<Vec<_>>()
+        );
+
+        let line = Translation {
+            from: (3, 1),
+            to: (3, 1),
+        };
+        assert_eq!(
+            vec![(3, 1)],
+            TranslationWalker::from_translation(&line).collect::<Vec<_>>()
+        );
+
+        let line = Translation {
+            from: (-2_000_000_000, 0),
+            to: (2_000_000_000, 1),
+        };
+        assert_eq!(
+            vec![line.from, line.to],
+            TranslationWalker::from_translation(&line).collect::<Vec<_>>()
+        );
+
+        for to in [(0, 5), (-5, 0), (5, 5), (-5, 5)] {
+            let line = Translation { from: (0, 0), to };
+            assert_eq!(
+                TranslationWalker::new(&line, Raster::Lattice).collect::<Vec<_>>(),
+                TranslationWalker::new(&line, Raster::Bresenham).collect::<Vec<_>>()
+            );
+        }
+    }
+
     #[test]
     fn test_part_1() {
         assert_eq!(
@@ -0,0 +1,267 @@
+use crate::sweep::{count_overlaps, cross, gcd, Segment};
+use crate::Translation;
+
+// Centered interval tree over the segments' x ranges. Each node keeps the
+// segments spanning its center, sorted both ways so a range query can stop at
+// the first one out of reach.
+struct Node {
+    center: i64,
+    by_start: Vec<usize>,
+    by_end: Vec<usize>,
+    left: Option<Box<Node>>,
+    right: Option<Box<Node>>,
+}
+
+impl Node {
+    fn build(mut ids: Vec<usize>, segments: &[Segment]) -> Option<Box<Node>> {
+        if ids.is_empty() {
+            return None;
+        }
+        ids.sort_unstable_by_key(|&i| {
+            let (a, b) = segments[i].x_range();
+            a + (b - a) / 2
+        });
+        let center = {
+            let (a, b) = segments[ids[ids.len() / 2]].x_range();
+            a + (b - a) / 2
+        };
+
+        let (mut left, mut right, mut here) = (Vec::new(), Vec::new(), Vec::new());
+        for i in ids {
+            match segments[i].x_range() {
+                (_, b) if b < center => left.push(i),
+                (a, _) if a > center => right.push(i),
+                _ => here.push(i),
+            }
+        }
+        let mut by_start = here.clone();
+        by_start.sort_unstable_by_key(|&i| segments[i].x_range().0);
+        let mut by_end = here;
+        by_end.sort_unstable_by_key(|&i| -segments[i].x_range().1);
+
+        Some(Box::new(Node {
+            center,
+            by_start,
+            by_end,
+            left: Node::build(left, segments),
+            right: Node::build(right, segments),
+        }))
+    }
+
+    fn query(&self, (a, b): (i64, i64), segments: &[Segment], found: &mut Vec<usize>) {
+        if b < self.center {
+            found.extend(
+                self.by_start
+                    .iter()
+                    .take_while(|&&i| segments[i].x_range().0 <= b),
+            );
+        } else if a > self.center {
+            found.extend(
+                self.by_end
+                    .iter()
+                    .take_while(|&&i| segments[i].x_range().1 >= a),
+            );
+        } else {
+            found.extend(&self.by_start);
+        }
+        if a < self.center {
+            if let Some(left) = &self.left {
+                left.query((a, b), segments, found);
+            }
+        }
+        if b > self.center {
+            if let Some(right) = &self.right {
+                right.query((a, b), segments, found);
+            }
+        }
+    }
+}
+
+pub struct Hazards<'a> {
+    lines: &'a [Translation],
+    segments: Vec<Segment>,
+    root: Option<Box<Node>>,
+}
+
+impl Hazards<'_> {
+    pub fn new(lines: &[Translation]) -> Hazards<'_> {
+        let segments = lines.iter().map(Segment::new).collect::<Vec<Segment>>();
+        let root = Node::build((0..segments.len()).collect(), &segments);
+        Hazards {
+            lines,
+            segments,
+            root,
+        }
+    }
+
+    // Segments whose bounding box meets the given one, in input order.
+    fn candidates(&self, x: (i64, i64), y: (i64, i64)) -> Vec<usize> {
+        let mut found = Vec::new();
+        if let Some(root) = &self.root {
+            root.query(x, &self.segments, &mut found);
+        }
+        found.retain(|&i| {
+            let (y_min, y_max) = self.segments[i].y_range();
+            y_max >= y.0 && y_min <= y.1
+        });
+        found.sort_unstable();
+        found
+    }
+
+    // Indices of the lines covering the lattice point `p`.
+    pub fn covering(&self, p: (i32, i32)) -> Vec<usize> {
+        let p = (p.0 as i64, p.1 as i64);
+        let mut found = self.candidates((p.0, p.0), (p.1, p.1));
+        found.retain(|&i| {
+            let segment = &self.segments[i];
+            let (start, end) = segment.span();
+            let pos = segment.position(p);
+            cross(segment.line.0, p) == segment.line.1 && start <= pos && pos <= end
+        });
+        found
+    }
+
+    // Indices of the lines sharing at least one covered point with `line`.
+    pub fn intersecting(&self, line: &Translation) -> Vec<usize> {
+        let segment = Segment::new(line);
+        let mut found = self.candidates(segment.x_range(), segment.y_range());
+        found.retain(|&i| {
+            let other = &self.segments[i];
+            if other.line == segment.line {
+                let ((a, b), (c, d)) = (segment.span(), other.span());
+                a <= d && c <= b
+            } else {
+                segment.crossing(other).is_some()
+            }
+        });
+        found
+    }
+
+    // Points covered by at least two lines within the inclusive rectangle.
+    pub fn overlaps_in(&self, from: (i32, i32), to: (i32, i32)) -> usize {
+        let x = (from.0.min(to.0), from.0.max(to.0));
+        let y = (from.1.min(to.1), from.1.max(to.1));
+        let clipped = self
+            .candidates((x.0 as i64, x.1 as i64), (y.0 as i64, y.1 as i64))
+            .iter()
+            .filter_map(|&i| clip(&self.lines[i], x, y))
+            .collect::<Vec<Translation>>();
+        count_overlaps(&clipped)
+    }
+}
+
+fn div_ceil(a: i64, b: i64) -> i64 {
+    -(-a).div_euclid(b)
+}
+
+// The part of a line inside the rectangle, still ending on lattice points.
+fn clip(line: &Translation, x: (i32, i32), y: (i32, i32)) -> Option<Translation> {
+    let from = (line.from.0 as i64, line.from.1 as i64);
+    let (dx, dy) = (line.to.0 as i64 - from.0, line.to.1 as i64 - from.1);
+    let steps = gcd(dx, dy);
+    let step = match steps {
+        0 => (0, 0),
+        g => (dx / g, dy / g),
+    };
+
+    let mut k = (0, steps);
+    for (start, d, (lo, hi)) in [(from.0, step.0, x), (from.1, step.1, y)] {
+        let (lo, hi) = (lo as i64 - start, hi as i64 - start);
+        match d {
+            0 if lo > 0 || hi < 0 => return None,
+            0 => (),
+            d if d > 0 => k = (k.0.max(div_ceil(lo, d)), k.1.min(hi.div_euclid(d))),
+            d => k = (k.0.max(div_ceil(-hi, -d)), k.1.min((-lo).div_euclid(-d))),
+        }
+    }
+
+    let at = |k: i64| ((from.0 + k * step.0) as i32, (from.1 + k * step.1) as i32);
+    match k.0 <= k.1 {
+        true => Some(Translation {
+            from: at(k.0),
+            to: at(k.1),
+        }),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate, TranslationWalker};
+    use std::collections::HashMap;
+
+    fn sample() -> Vec<Translation> {
+        generate(
+            r"0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2
+1,0 -> 7,3",
+        )
+    }
+
+    #[test]
+    fn test_queries_match_walker() {
+        let lines = sample();
+        let hazards = Hazards::new(&lines);
+
+        let mut coverage: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        lines.iter().enumerate().for_each(|(i, l)| {
+            TranslationWalker::from_translation(l)
+                .for_each(|p| coverage.entry(p).or_default().push(i))
+        });
+
+        for x in -1..=10 {
+            for y in -1..=10 {
+                let expected = coverage.get(&(x, y)).cloned().unwrap_or_default();
+                assert_eq!(expected, hazards.covering((x, y)));
+            }
+        }
+
+        for (from, to) in [
+            ((0, 0), (9, 9)),
+            ((2, 1), (6, 5)),
+            ((3, 3), (3, 3)),
+            ((7, 9), (4, 6)),
+        ] {
+            let expected = coverage
+                .iter()
+                .filter(|(p, v)| {
+                    v.len() > 1
+                        && p.0 >= from.0.min(to.0)
+                        && p.0 <= from.0.max(to.0)
+                        && p.1 >= from.1.min(to.1)
+                        && p.1 <= from.1.max(to.1)
+                })
+                .count();
+            assert_eq!(expected, hazards.overlaps_in(from, to));
+        }
+    }
+
+    #[test]
+    fn test_intersecting() {
+        let lines = sample();
+        let hazards = Hazards::new(&lines);
+
+        for line in generate("0,4 -> 4,4\n1,0 -> 5,2\n9,0 -> 0,9\n8,8 -> 8,8\n4,-2 -> 4,12") {
+            let points = TranslationWalker::from_translation(&line).collect::<Vec<_>>();
+            let expected = (0..lines.len())
+                .filter(|&i| {
+                    TranslationWalker::from_translation(&lines[i]).any(|p| points.contains(&p))
+                })
+                .collect::<Vec<usize>>();
+            assert_eq!(expected, hazards.intersecting(&line));
+        }
+        assert_eq!(
+            vec![5, 10],
+            hazards.intersecting(&generate("1,0 -> 5,2")[0])
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use crate::Translation;
+use std::collections::{HashMap, HashSet};
+
+pub type Point = (i64, i64);
+
+// The supporting line of a segment, as its primitive direction and the cross
+// product every lattice point on it shares with that direction.
+pub type LineKey = (Point, i128);
+
+pub struct Segment {
+    pub from: Point,
+    pub to: Point,
+    pub line: LineKey,
+}
+
+pub fn gcd(a: i64, b: i64) -> i64 {
+    match b {
+        0 => a.abs(),
+        _ => gcd(b, a % b),
+    }
+}
+
+pub fn cross(a: Point, b: Point) -> i128 {
+    a.0 as i128 * b.1 as i128 - a.1 as i128 * b.0 as i128
+}
+
+impl Segment {
+    pub fn new(t: &Translation) -> Segment {
+        let from = (t.from.0 as i64, t.from.1 as i64);
+        let to = (t.to.0 as i64, t.to.1 as i64);
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        // A single point is treated as a zero length horizontal segment.
+        let direction = match gcd(dx, dy) {
+            0 => (1, 0),
+            g if dx < 0 || (dx == 0 && dy < 0) => (-dx / g, -dy / g),
+            g => (dx / g, dy / g),
+        };
+        Segment {
+            from,
+            to,
+            line: (direction, cross(direction, from)),
+        }
+    }
+
+    // Position of a lattice point along the supporting line, in steps.
+    pub fn position(&self, p: Point) -> i64 {
+        let d = (self.line.0 .0 as i128, self.line.0 .1 as i128);
+        let dot = d.0 * p.0 as i128 + d.1 * p.1 as i128;
+        dot.div_euclid(d.0 * d.0 + d.1 * d.1) as i64
+    }
+
+    pub fn span(&self) -> (i64, i64) {
+        let (a, b) = (self.position(self.from), self.position(self.to));
+        (a.min(b), a.max(b))
+    }
+
+    pub fn x_range(&self) -> (i64, i64) {
+        (self.from.0.min(self.to.0), self.from.0.max(self.to.0))
+    }
+
+    pub fn y_range(&self) -> (i64, i64) {
+        (self.from.1.min(self.to.1), self.from.1.max(self.to.1))
+    }
+
+    // The lattice point where two segments on different lines cross, if any.
+    pub fn crossing(&self, other: &Segment) -> Option<Point> {
+        let r = (self.to.0 - self.from.0, self.to.1 - self.from.1);
+        let q = (other.to.0 - other.from.0, other.to.1 - other.from.1);
+        let (r, q) = (
+            if r == (0, 0) { self.line.0 } else { r },
+            if q == (0, 0) { other.line.0 } else { q },
+        );
+        let denom = cross(r, q);
+        let pq = (other.from.0 - self.from.0, other.from.1 - self.from.1);
+        let (mut s, mut u) = (cross(pq, q), cross(pq, r));
+        let denom = match denom {
+            0 => return None,
+            d if d < 0 => {
+                s = -s;
+                u = -u;
+                -d
+            }
+            d => d,
+        };
+        let self_len = if self.from == self.to { 0 } else { denom };
+        let other_len = if other.from == other.to { 0 } else { denom };
+        if s < 0 || s > self_len || u < 0 || u > other_len {
+            return None;
+        }
+        let x = self.from.0 as i128 * denom + r.0 as i128 * s;
+        let y = self.from.1 as i128 * denom + r.1 as i128 * s;
+        match x % denom == 0 && y % denom == 0 {
+            true => Some(((x / denom) as i64, (y / denom) as i64)),
+            false => None,
+        }
+    }
+}
+
+// Counts lattice points covered by at least two segments without walking
+// them. Collinear overlaps are measured per supporting line with a 1D sweep,
+// and crossings between lines come from a sweep over x that only pairs up
+// segments whose x ranges overlap. Memory grows with the number of segments
+// and crossings, not with the segments' length.
+pub fn count_overlaps(input: &[Translation]) -> usize {
+    let segments = input.iter().map(Segment::new).collect::<Vec<Segment>>();
+
+    let mut by_line: HashMap<LineKey, Vec<(i64, i64)>> = HashMap::new();
+    segments
+        .iter()
+        .for_each(|s| by_line.entry(s.line).or_default().push(s.span()));
+
+    let mut count = 0;
+    let mut shared: HashMap<LineKey, Vec<(i64, i64)>> = HashMap::new();
+    for (line, spans) in by_line {
+        let mut events = spans
+            .iter()
+            .flat_map(|&(a, b)| [(a, 1), (b + 1, -1)])
+            .collect::<Vec<(i64, i32)>>();
+        events.sort_unstable();
+
+        let mut depth = 0;
+        let mut start = 0;
+        let mut intervals = Vec::new();
+        for (pos, delta) in events {
+            let before = depth;
+            depth += delta;
+            if before < 2 && depth >= 2 {
+                start = pos;
+            } else if before >= 2 && depth < 2 {
+                intervals.push((start, pos - 1));
+                count += (pos - start) as usize;
+            }
+        }
+        if !intervals.is_empty() {
+            shared.insert(line, intervals);
+        }
+    }
+
+    let covered = |line: &LineKey, pos: i64| {
+        shared.get(line).is_some_and(|intervals| {
+            let i = intervals.partition_point(|&(_, end)| end < pos);
+            i < intervals.len() && intervals[i].0 <= pos
+        })
+    };
+
+    let mut order = (0..segments.len()).collect::<Vec<usize>>();
+    order.sort_unstable_by_key(|&i| segments[i].x_range().0);
+
+    // A crossing may sit in the collinear overlaps of other lines, which then
+    // cross the pair there too, so keep every line through it. Such a point
+    // is already counted once per overlap it sits in.
+    let mut crossings: HashMap<Point, HashSet<(LineKey, i64)>> = HashMap::new();
+    let mut active: Vec<usize> = Vec::new();
+    for i in order {
+        let segment = &segments[i];
+        let (x_min, _) = segment.x_range();
+        active.retain(|&j| segments[j].x_range().1 >= x_min);
+
+        let (y_min, y_max) = segment.y_range();
+        for &j in &active {
+            let other = &segments[j];
+            let (oy_min, oy_max) = other.y_range();
+            if other.line.0 == segment.line.0 || oy_max < y_min || oy_min > y_max {
+                continue;
+            }
+            if let Some(p) = segment.crossing(other) {
+                let lines = crossings.entry(p).or_default();
+                lines.insert((segment.line, segment.position(p)));
+                lines.insert((other.line, other.position(p)));
+            }
+        }
+        active.push(i);
+    }
+
+    crossings.values().fold(count, |count, lines| {
+        match lines
+            .iter()
+            .filter(|(line, pos)| covered(line, *pos))
+            .count()
+        {
+            0 => count + 1,
+            shared => count - (shared - 1),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TranslationWalker;
+
+    fn walk_count(input: &[Translation]) -> usize {
+        let mut map: HashMap<(i32, i32), usize> = HashMap::new();
+        input
+            .iter()
+            .flat_map(TranslationWalker::from_translation)
+            .for_each(|p| *map.entry(p).or_insert(0) += 1);
+        map.values().filter(|&&v| v > 1).count()
+    }
+
+    #[test]
+    fn test_matches_walker() {
+        let mut seed = 11u64;
+        let mut next = |m: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % m) as i32 - (m / 2) as i32
+        };
+
+        for round in 0..100 {
+            let lines = (0..40)
+                .map(|_| {
+                    let from = (next(21), next(21));
+                    let to = match round % 2 {
+                        0 => (next(21), next(21)),
+                        _ => {
+                            let (len, dx, dy) = (next(21), next(3), next(3));
+                            (from.0 + len * dx, from.1 + len * dy)
+                        }
+                    };
+                    Translation { from, to }
+                })
+                .collect::<Vec<Translation>>();
+            assert_eq!(walk_count(&lines), count_overlaps(&lines));
+        }
+    }
+
+    #[test]
+    fn test_large_coordinates() {
+        let lines = vec![
+            Translation {
+                from: (0, 0),
+                to: (2_000_000_000, 0),
+            },
+            Translation {
+                from: (1_000_000_000, -5),
+                to: (1_000_000_000, 5),
+            },
+            Translation {
+                from: (1_500_000_000, 0),
+                to: (-1_500_000_000, 0),
+            },
+            Translation {
+                from: (0, -1_000_000_000),
+                to: (2_000_000_000, 1_000_000_000),
+            },
+        ];
+        assert_eq!(1_500_000_001, count_overlaps(&lines));
+        assert_eq!(1_500_000_001, crate::part_1(&lines));
+    }
+}
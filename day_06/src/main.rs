@@ -1,4 +1,10 @@
+mod model;
+mod series;
+mod stochastic;
+
+use model::Timers;
 use std::collections::VecDeque;
+use std::env;
 use std::fs;
 use std::time::Instant;
 
@@ -11,12 +17,12 @@ fn generate(input: &str) -> Generated {
         .map(|l| l.split(',').map(|v| v.parse().unwrap()).collect())
         .collect::<Vec<Vec<usize>>>();
     let mut collection = [0; 9];
-    values[0].iter().for_each(|&v| collection[v as usize] += 1);
+    values[0].iter().for_each(|&v| collection[v] += 1);
     VecDeque::from(collection)
 }
 
-fn part_1(input: &mut Generated) -> u128 {
-    for _ in 0..80 {
+fn simulate(input: &mut Generated, days: usize) -> u128 {
+    for _ in 0..days {
         let value = input.pop_front().unwrap();
         input[6] += value;
         input.push_back(value);
@@ -25,14 +31,12 @@ fn part_1(input: &mut Generated) -> u128 {
     input.iter().sum::<u128>()
 }
 
-fn part_2(input: &mut Generated) -> u128 {
-    for _ in 0..256 {
-        let value = input.pop_front().unwrap();
-        input[6] += value;
-        input.push_back(value);
-    }
+fn part_1(input: &mut Generated) -> u128 {
+    simulate(input, 80)
+}
 
-    input.iter().sum::<u128>()
+fn part_2(input: &mut Generated) -> u128 {
+    simulate(input, 256)
 }
 
 fn main() {
@@ -56,6 +60,70 @@ fn main() {
         res2,
         res2_stop.duration_since(res2_start)
     );
+
+    // e.g. `--days 1000000 --reset 6 --newborn 8 --modulus 1000000007`,
+    // `--series growth.csv --days 256`,
+    // `--monte-carlo 100 --days 80 --seed 1 --cycle-dist 5-7 --mortality 0.01`
+    let args = env::args().collect::<Vec<String>>();
+    let arg = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .map(|pos| args.get(pos + 1).expect("Missing argument value").as_str())
+    };
+    let defaults = Timers::default();
+    let timers = Timers {
+        reset: arg("--reset").map_or(defaults.reset, |v| v.parse().unwrap()),
+        newborn: arg("--newborn").map_or(defaults.newborn, |v| v.parse().unwrap()),
+    };
+    let counts = generate(&content).into_iter().collect::<Vec<u128>>();
+
+    if let Some(path) = arg("--series") {
+        let days = arg("--days").map_or(256, |v| v.parse().unwrap());
+        let csv = series::to_csv(&series::series(&counts, timers, days));
+        fs::write(path, csv).expect("write failed");
+        println!("Growth rate: {:.6} per day", series::growth_rate(timers));
+    }
+    if let Some(runs) = arg("--monte-carlo") {
+        let days = arg("--days").map_or(80, |v| v.parse().unwrap());
+        let defaults = stochastic::Config::from(timers);
+        let config = stochastic::Config::new(
+            arg("--cycle-dist").map_or(defaults.cycle, |v| v.parse().unwrap()),
+            arg("--newborn-dist").map_or(defaults.newborn, |v| v.parse().unwrap()),
+            arg("--mortality").map_or(0.0, |v| v.parse().unwrap()),
+        )
+        .unwrap();
+        let seed = arg("--seed").map_or(0, |v| v.parse().unwrap());
+
+        match stochastic::monte_carlo(&counts, &config, days, runs.parse().unwrap(), seed) {
+            Some(summary) => {
+                println!(
+                    "Monte Carlo after {} days over {} runs: mean {:.2}, variance {:.2}",
+                    days, summary.runs, summary.mean, summary.variance
+                );
+                for (p, fish) in summary.percentiles {
+                    println!("  p{}: {}", p, fish);
+                }
+            }
+            None => println!("Monte Carlo needs at least one run"),
+        }
+        println!(
+            "Deterministic: {}",
+            model::population(&counts, timers, days as u64, None)
+        );
+    }
+    if let Some(days) = arg("--days").filter(|_| arg("--monte-carlo").is_none()) {
+        let days = days.parse::<u64>().unwrap();
+        let modulus = arg("--modulus").map(|v| model::parse_modulus(v).unwrap());
+
+        let start = Instant::now();
+        let fish = model::population(&counts, timers, days, modulus.as_ref());
+        let stop = Instant::now();
+        match modulus {
+            Some(m) => println!("Fish after {} days (mod {}): {}", days, m, fish),
+            None => println!("Fish after {} days: {}", days, fish),
+        }
+        println!("Resolved in: {:?}", stop.duration_since(start));
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,167 @@
+use num_bigint::BigUint;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timers {
+    pub reset: usize,
+    pub newborn: usize,
+}
+
+impl Default for Timers {
+    fn default() -> Timers {
+        Timers {
+            reset: 6,
+            newborn: 8,
+        }
+    }
+}
+
+type Matrix = Vec<Vec<BigUint>>;
+
+// Results are taken modulo a positive number, as there is no remainder of
+// a division by zero.
+pub fn parse_modulus(s: &str) -> Result<BigUint, String> {
+    match s.trim().parse::<BigUint>() {
+        Ok(m) if m > BigUint::default() => Ok(m),
+        _ => Err(format!("Modulus not a positive number: {}", s)),
+    }
+}
+
+fn reduce(value: BigUint, modulus: Option<&BigUint>) -> BigUint {
+    match modulus {
+        Some(m) => value % m,
+        None => value,
+    }
+}
+
+fn multiply(a: &Matrix, b: &Matrix, modulus: Option<&BigUint>) -> Matrix {
+    let size = a.len();
+    (0..size)
+        .map(|row| {
+            (0..size)
+                .map(|col| {
+                    let sum = (0..size)
+                        .filter(|&k| a[row][k].bits() > 0 && b[k][col].bits() > 0)
+                        .fold(BigUint::from(0u32), |sum, k| sum + &a[row][k] * &b[k][col]);
+                    reduce(sum, modulus)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// `step[to][from]` is how many fish with timer `to` one fish with timer
+// `from` turns into after a day.
+fn transition(timers: Timers) -> Matrix {
+    let size = timers.reset.max(timers.newborn) + 1;
+    let mut step = vec![vec![BigUint::from(0u32); size]; size];
+    (1..size).for_each(|from| step[from - 1][from] += 1u32);
+    step[timers.reset][0] += 1u32;
+    step[timers.newborn][0] += 1u32;
+    step
+}
+
+// Number of fish after `days`, starting from `counts[t]` fish with timer `t`.
+// The daily step is a linear map, so its `days`-th power is built by repeated
+// squaring in O(log days) matrix products. With a modulus every product is
+// reduced, which keeps the numbers small for day counts like 10^18 whose
+// exact answer would not fit in memory.
+pub fn population(
+    counts: &[u128],
+    timers: Timers,
+    days: u64,
+    modulus: Option<&BigUint>,
+) -> BigUint {
+    let mut base = transition(timers);
+    let size = base.len();
+    assert!(
+        counts.iter().skip(size).all(|&c| c == 0),
+        "Timer larger than the model"
+    );
+
+    let mut power = (0..size)
+        .map(|i| (0..size).map(|j| BigUint::from((i == j) as u32)).collect())
+        .collect::<Matrix>();
+    let mut days = days;
+    while days > 0 {
+        if days & 1 == 1 {
+            power = multiply(&power, &base, modulus);
+        }
+        days >>= 1;
+        if days > 0 {
+            base = multiply(&base, &base, modulus);
+        }
+    }
+
+    let total = (0..size)
+        .flat_map(|to| (0..size).map(move |from| (to, from)))
+        .filter(|&(_, from)| from < counts.len())
+        .fold(BigUint::from(0u32), |total, (to, from)| {
+            total + &power[to][from] * counts[from]
+        });
+    reduce(total, modulus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate, simulate};
+
+    // Day by day reference for any timers.
+    fn naive(counts: &[u128], timers: Timers, days: u64) -> u128 {
+        let mut counts = counts.to_vec();
+        counts.resize(timers.reset.max(timers.newborn) + 1, 0);
+        for _ in 0..days {
+            let spawning = counts.remove(0);
+            counts.push(0);
+            counts[timers.reset] += spawning;
+            counts[timers.newborn] += spawning;
+        }
+        counts.iter().sum()
+    }
+
+    #[test]
+    fn test_matches_simulation() {
+        let counts = generate("3,4,3,1,2").into_iter().collect::<Vec<u128>>();
+        for days in [0, 1, 18, 80, 256] {
+            assert_eq!(
+                BigUint::from(simulate(&mut generate("3,4,3,1,2"), days)),
+                population(&counts, Timers::default(), days as u64, None)
+            );
+        }
+
+        for (reset, newborn) in [(6, 8), (2, 5), (4, 1), (0, 3), (3, 3)] {
+            let timers = Timers { reset, newborn };
+            for days in [0, 1, 7, 30, 61] {
+                assert_eq!(
+                    BigUint::from(naive(&[0, 2, 1, 1], timers, days)),
+                    population(&[0, 2, 1, 1], timers, days, None)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_days() {
+        let counts = generate("3,4,3,1,2").into_iter().collect::<Vec<u128>>();
+        let exact = population(&counts, Timers::default(), 2000, None);
+        assert!(exact > BigUint::from(u128::MAX));
+
+        let modulus = BigUint::from(1_000_000_007u32);
+        assert_eq!(
+            &exact % &modulus,
+            population(&counts, Timers::default(), 2000, Some(&modulus))
+        );
+        assert!(
+            population(
+                &counts,
+                Timers::default(),
+                1_000_000_000_000_000_000,
+                Some(&modulus)
+            ) < modulus
+        );
+
+        assert_eq!(Ok(modulus), parse_modulus("1000000007"));
+        assert!(parse_modulus("0").is_err());
+        assert!(parse_modulus("-7").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::model::Timers;
+use num_bigint::BigUint;
+use std::fmt::Write;
+
+// Timer buckets for every day from 0 to `days`, kept exact past u128.
+pub fn series(counts: &[u128], timers: Timers, days: usize) -> Vec<Vec<BigUint>> {
+    let size = timers.reset.max(timers.newborn) + 1;
+    assert!(
+        counts.iter().skip(size).all(|&c| c == 0),
+        "Timer larger than the model"
+    );
+    let mut today = (0..size)
+        .map(|t| BigUint::from(counts.get(t).cloned().unwrap_or(0)))
+        .collect::<Vec<BigUint>>();
+
+    let mut days_counts = vec![today.clone()];
+    for _ in 0..days {
+        let spawning = today.remove(0);
+        today.push(BigUint::from(0u32));
+        today[timers.reset] += &spawning;
+        today[timers.newborn] += &spawning;
+        days_counts.push(today.clone());
+    }
+    days_counts
+}
+
+// The fish spawning on a day are those that spawned `reset + 1` days earlier
+// plus those born `newborn + 1` days earlier, so the school settles into
+// growing by the root above 1 of `x^-(reset+1) + x^-(newborn+1) = 1`. When
+// both periods share a factor the daily ratio keeps oscillating around it.
+pub fn growth_rate(timers: Timers) -> f64 {
+    let f = |x: f64| x.powi(-(timers.reset as i32 + 1)) + x.powi(-(timers.newborn as i32 + 1));
+    let (mut lo, mut hi) = (1.0, 2.0);
+    for _ in 0..64 {
+        let mid = (lo + hi) / 2.0;
+        match f(mid) > 1.0 {
+            true => lo = mid,
+            false => hi = mid,
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+fn ratio(a: &BigUint, b: &BigUint) -> f64 {
+    // Keep the top bits only so the division stays in f64 range.
+    let shift = a.bits().max(b.bits()).saturating_sub(64);
+    let (a, b) = ((a >> shift).to_u64_digits(), (b >> shift).to_u64_digits());
+    a.first().cloned().unwrap_or(0) as f64 / b.first().cloned().unwrap_or(0) as f64
+}
+
+// One line per day with each bucket, the total and its ratio to the day
+// before.
+pub fn to_csv(series: &[Vec<BigUint>]) -> String {
+    let size = series.first().map_or(0, |day| day.len());
+    let mut csv = String::from("day");
+    (0..size).for_each(|t| write!(csv, ",timer_{}", t).unwrap());
+    csv.push_str(",total,growth\n");
+
+    let mut previous: Option<BigUint> = None;
+    for (day, counts) in series.iter().enumerate() {
+        write!(csv, "{}", day).unwrap();
+        counts.iter().for_each(|c| write!(csv, ",{}", c).unwrap());
+        let total = counts.iter().sum::<BigUint>();
+        match &previous {
+            Some(p) => writeln!(csv, ",{},{:.6}", total, ratio(&total, p)).unwrap(),
+            None => writeln!(csv, ",{},", total).unwrap(),
+        }
+        previous = Some(total);
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+    use crate::model::population;
+
+    #[test]
+    fn test_series() {
+        let counts = generate("3,4,3,1,2").into_iter().collect::<Vec<u128>>();
+        let days = series(&counts, Timers::default(), 80);
+        assert_eq!(81, days.len());
+        assert_eq!(BigUint::from(26u32), days[18].iter().sum::<BigUint>());
+        assert_eq!(BigUint::from(5934u32), days[80].iter().sum::<BigUint>());
+
+        let timers = Timers {
+            reset: 2,
+            newborn: 4,
+        };
+        let days = series(&counts, timers, 40);
+        assert_eq!(
+            population(&counts, timers, 40, None),
+            days[40].iter().sum::<BigUint>()
+        );
+
+        let csv = to_csv(&days[..2]);
+        assert_eq!(
+            "day,timer_0,timer_1,timer_2,timer_3,timer_4,total,growth\n\
+             0,0,1,1,2,1,5,\n\
+             1,1,1,2,1,0,5,1.000000\n",
+            csv
+        );
+    }
+
+    #[test]
+    fn test_growth_rate() {
+        let counts = generate("3,4,3,1,2").into_iter().collect::<Vec<u128>>();
+        for (reset, newborn) in [(6, 8), (2, 4), (4, 0), (4, 1)] {
+            let timers = Timers { reset, newborn };
+            let days = series(&counts, timers, 2000);
+            let total = |d: usize| days[d].iter().sum::<BigUint>();
+            let observed = ratio(&total(2000), &total(1999));
+            assert!((observed - growth_rate(timers)).abs() < 1e-9);
+        }
+        assert!(
+            (growth_rate(Timers {
+                reset: 0,
+                newborn: 0
+            }) - 2.0)
+                .abs()
+                < 1e-12
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use crate::model::Timers;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::str::FromStr;
+
+// How a timer is drawn: `6`, `5-7` (uniform, inclusive) or `6:0.9,7:0.1`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Distribution {
+    Fixed(usize),
+    Uniform(usize, usize),
+    Weighted(Vec<(usize, f64)>),
+}
+
+impl FromStr for Distribution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |v: &str| {
+            v.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("Incorrect timer: {}", v))
+        };
+        if s.contains(':') {
+            let weights = s
+                .split(',')
+                .map(|pair| {
+                    let (timer, weight) = pair
+                        .split_once(':')
+                        .ok_or(format!("Incorrect weight: {}", pair))?;
+                    let weight = weight
+                        .trim()
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|w| *w >= 0.0)
+                        .ok_or(format!("Incorrect weight: {}", pair))?;
+                    Ok((number(timer)?, weight))
+                })
+                .collect::<Result<Vec<(usize, f64)>, String>>()?;
+            match weights.iter().map(|(_, w)| w).sum::<f64>() > 0.0 {
+                true => Ok(Distribution::Weighted(weights)),
+                false => Err("Weights sum up to zero".to_string()),
+            }
+        } else if let Some((a, b)) = s.split_once('-') {
+            match (number(a)?, number(b)?) {
+                (a, b) if a <= b => Ok(Distribution::Uniform(a, b)),
+                _ => Err(format!("Empty range: {}", s)),
+            }
+        } else {
+            Ok(Distribution::Fixed(number(s)?))
+        }
+    }
+}
+
+impl Distribution {
+    pub fn sample(&self, rng: &mut StdRng) -> usize {
+        match self {
+            Distribution::Fixed(t) => *t,
+            Distribution::Uniform(a, b) => rng.gen_range(*a..=*b),
+            Distribution::Weighted(weights) => {
+                let total = weights.iter().map(|(_, w)| w).sum::<f64>();
+                let mut pick = rng.gen::<f64>() * total;
+                for &(timer, weight) in weights {
+                    if pick < weight {
+                        return timer;
+                    }
+                    pick -= weight;
+                }
+                weights.iter().rev().find(|(_, w)| *w > 0.0).unwrap().0
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    // Timer a fish restarts from after spawning, drawn once per fish.
+    pub cycle: Distribution,
+    // Timer a newborn starts from.
+    pub newborn: Distribution,
+    // Chance of every fish dying on any given day.
+    pub mortality: f64,
+}
+
+impl Config {
+    pub fn new(
+        cycle: Distribution,
+        newborn: Distribution,
+        mortality: f64,
+    ) -> Result<Config, String> {
+        match (0.0..=1.0).contains(&mortality) {
+            true => Ok(Config {
+                cycle,
+                newborn,
+                mortality,
+            }),
+            false => Err(format!("Mortality not within 0 and 1: {}", mortality)),
+        }
+    }
+}
+
+impl From<Timers> for Config {
+    fn from(timers: Timers) -> Config {
+        Config {
+            cycle: Distribution::Fixed(timers.reset),
+            newborn: Distribution::Fixed(timers.newborn),
+            mortality: 0.0,
+        }
+    }
+}
+
+struct Fish {
+    timer: usize,
+    cycle: usize,
+}
+
+// One run over individual fish, so it grows with the population; keep the
+// day count around part 1 sizes.
+pub fn simulate(counts: &[u128], config: &Config, days: usize, rng: &mut StdRng) -> usize {
+    let mut school = Vec::new();
+    for (timer, &count) in counts.iter().enumerate() {
+        for _ in 0..count {
+            let cycle = config.cycle.sample(rng);
+            school.push(Fish { timer, cycle });
+        }
+    }
+
+    for _ in 0..days {
+        let mut born = 0;
+        school.retain_mut(|fish| {
+            if config.mortality > 0.0 && rng.gen_bool(config.mortality) {
+                return false;
+            }
+            match fish.timer {
+                0 => {
+                    fish.timer = fish.cycle;
+                    born += 1;
+                }
+                _ => fish.timer -= 1,
+            }
+            true
+        });
+        for _ in 0..born {
+            let timer = config.newborn.sample(rng);
+            let cycle = config.cycle.sample(rng);
+            school.push(Fish { timer, cycle });
+        }
+    }
+
+    school.len()
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub runs: usize,
+    pub mean: f64,
+    pub variance: f64,
+    pub percentiles: Vec<(f64, usize)>,
+}
+
+// Nearest rank percentiles, with the sample variance of the runs. There is
+// nothing to summarize without any.
+pub fn summarize(mut samples: Vec<usize>, percentiles: &[f64]) -> Option<Summary> {
+    if samples.is_empty() {
+        return None;
+    }
+    samples.sort_unstable();
+    let runs = samples.len();
+    let mean = samples.iter().sum::<usize>() as f64 / runs as f64;
+    let variance = match runs {
+        1 => 0.0,
+        _ => {
+            samples
+                .iter()
+                .map(|&s| (s as f64 - mean).powi(2))
+                .sum::<f64>()
+                / (runs - 1) as f64
+        }
+    };
+    let percentiles = percentiles
+        .iter()
+        .map(|&p| {
+            let rank = ((p / 100.0 * runs as f64).ceil() as usize).clamp(1, runs);
+            (p, samples[rank - 1])
+        })
+        .collect();
+
+    Some(Summary {
+        runs,
+        mean,
+        variance,
+        percentiles,
+    })
+}
+
+pub fn monte_carlo(
+    counts: &[u128],
+    config: &Config,
+    days: usize,
+    runs: usize,
+    seed: u64,
+) -> Option<Summary> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let samples = (0..runs)
+        .map(|_| simulate(counts, config, days, &mut rng))
+        .collect();
+    summarize(samples, &[5.0, 25.0, 50.0, 75.0, 95.0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+    use num_bigint::BigUint;
+
+    fn sample() -> Vec<u128> {
+        generate("3,4,3,1,2").into_iter().collect()
+    }
+
+    #[test]
+    fn test_deterministic_reference() {
+        let config = Config::from(Timers::default());
+        let summary = monte_carlo(&sample(), &config, 80, 3, 1).unwrap();
+        assert_eq!(5934.0, summary.mean);
+        assert_eq!(0.0, summary.variance);
+
+        let timers = Timers {
+            reset: 3,
+            newborn: 5,
+        };
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(
+            crate::model::population(&sample(), timers, 40, None),
+            BigUint::from(simulate(&sample(), &Config::from(timers), 40, &mut rng))
+        );
+    }
+
+    #[test]
+    fn test_random_runs() {
+        let config =
+            Config::new("5-7".parse().unwrap(), "8:0.5,9:0.5".parse().unwrap(), 0.02).unwrap();
+        let a = monte_carlo(&sample(), &config, 60, 50, 7).unwrap();
+        assert_eq!(
+            Some(&a),
+            monte_carlo(&sample(), &config, 60, 50, 7).as_ref()
+        );
+        assert_ne!(
+            Some(&a),
+            monte_carlo(&sample(), &config, 60, 50, 8).as_ref()
+        );
+        assert!(a.variance > 0.0);
+        assert!(a.percentiles.windows(2).all(|w| w[0].1 <= w[1].1));
+
+        // Dying fish can only make the school smaller than the same cycles
+        // without deaths.
+        let immortal = Config {
+            mortality: 0.0,
+            ..config.clone()
+        };
+        assert!(a.mean < monte_carlo(&sample(), &immortal, 60, 50, 7).unwrap().mean);
+
+        let doomed = Config {
+            mortality: 1.0,
+            ..config
+        };
+        assert_eq!(0.0, monte_carlo(&sample(), &doomed, 1, 5, 7).unwrap().mean);
+        assert_eq!(None, monte_carlo(&sample(), &doomed, 1, 0, 7));
+
+        let fixed = || Distribution::Fixed(6);
+        assert!(Config::new(fixed(), fixed(), 1.5).is_err());
+        assert!(Config::new(fixed(), fixed(), -0.1).is_err());
+        assert!(Config::new(fixed(), fixed(), f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_summarize() {
+        let summary = summarize(vec![5, 1, 4, 2, 3], &[0.0, 20.0, 50.0, 100.0]).unwrap();
+        assert_eq!(3.0, summary.mean);
+        assert_eq!(2.5, summary.variance);
+        assert_eq!(
+            vec![(0.0, 1), (20.0, 1), (50.0, 3), (100.0, 5)],
+            summary.percentiles
+        );
+        assert_eq!(None, summarize(Vec::new(), &[50.0]));
+    }
+
+    #[test]
+    fn test_parse_distribution() {
+        assert_eq!(Ok(Distribution::Fixed(6)), "6".parse());
+        assert_eq!(Ok(Distribution::Uniform(5, 7)), "5-7".parse());
+        assert_eq!(
+            Ok(Distribution::Weighted(vec![(6, 0.9), (7, 0.1)])),
+            "6:0.9,7:0.1".parse()
+        );
+        assert!("7-5".parse::<Distribution>().is_err());
+        assert!("6:0".parse::<Distribution>().is_err());
+        assert!("x".parse::<Distribution>().is_err());
+    }
+}
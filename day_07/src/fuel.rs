@@ -0,0 +1,146 @@
+pub trait FuelCost {
+    // Fuel one crab burns moving `distance` steps. Must be convex in the
+    // distance for the search below to find the optimum.
+    fn cost(&self, distance: i64) -> i128;
+
+    fn total(&self, crabs: &[i64], at: i64) -> i128 {
+        crabs.iter().map(|c| self.cost((c - at).abs())).sum()
+    }
+
+    // Alignments among which an optimal one is found. Any convex cost makes
+    // the total convex too, so a ternary search over the crabs' span works.
+    fn candidates(&self, crabs: &[i64]) -> Vec<i64> {
+        let (mut lo, mut hi) = (*crabs.iter().min().unwrap(), *crabs.iter().max().unwrap());
+        while hi - lo > 2 {
+            let m1 = lo + (hi - lo) / 3;
+            let m2 = hi - (hi - lo) / 3;
+            match self.total(crabs, m1).cmp(&self.total(crabs, m2)) {
+                std::cmp::Ordering::Less => hi = m2 - 1,
+                std::cmp::Ordering::Greater => lo = m1 + 1,
+                std::cmp::Ordering::Equal => (lo, hi) = (m1, m2),
+            }
+        }
+        (lo..=hi).collect()
+    }
+}
+
+pub struct Linear;
+
+impl FuelCost for Linear {
+    fn cost(&self, distance: i64) -> i128 {
+        distance as i128
+    }
+
+    // Any median minimizes the sum of distances.
+    fn candidates(&self, crabs: &[i64]) -> Vec<i64> {
+        let mut crabs = crabs.to_vec();
+        let middle = crabs.len() / 2;
+        vec![*crabs.select_nth_unstable(middle).1]
+    }
+}
+
+// Floor of the mean, without overflowing on large inputs.
+fn mean(crabs: &[i64]) -> i64 {
+    let sum = crabs.iter().map(|&c| c as i128).sum::<i128>();
+    sum.div_euclid(crabs.len() as i128) as i64
+}
+
+pub struct Triangular;
+
+impl FuelCost for Triangular {
+    fn cost(&self, distance: i64) -> i128 {
+        let n = distance as i128;
+        n * (n + 1) / 2
+    }
+
+    // The total's slope is `n * (x - mean)` plus at most `n / 2` from the
+    // linear half of the cost, so the real optimum is within 1/2 of the mean.
+    fn candidates(&self, crabs: &[i64]) -> Vec<i64> {
+        let mean = mean(crabs);
+        (mean - 1..=mean + 2).collect()
+    }
+}
+
+pub struct Quadratic;
+
+impl FuelCost for Quadratic {
+    fn cost(&self, distance: i64) -> i128 {
+        let n = distance as i128;
+        n * n
+    }
+
+    fn candidates(&self, crabs: &[i64]) -> Vec<i64> {
+        let mean = mean(crabs);
+        vec![mean, mean + 1]
+    }
+}
+
+// A user supplied cost, e.g. `Convex(|d| (d as i128).pow(3))`.
+pub struct Convex<F: Fn(i64) -> i128>(pub F);
+
+impl<F: Fn(i64) -> i128> FuelCost for Convex<F> {
+    fn cost(&self, distance: i64) -> i128 {
+        (self.0)(distance)
+    }
+}
+
+// The cheapest alignment and the fuel it takes.
+pub fn align<C: FuelCost + ?Sized>(crabs: &[i64], cost: &C) -> (i64, i128) {
+    cost.candidates(crabs)
+        .into_iter()
+        .map(|at| (at, cost.total(crabs, at)))
+        .min_by_key(|&(at, fuel)| (fuel, at))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force<C: FuelCost>(crabs: &[i64], cost: &C) -> i128 {
+        let min = *crabs.iter().min().unwrap();
+        let max = *crabs.iter().max().unwrap();
+        (min..=max).map(|at| cost.total(crabs, at)).min().unwrap()
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut seed = 5u64;
+        let mut next = |m: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % m) as i64 - (m / 2) as i64
+        };
+        let cube = Convex(|d| (d as i128).pow(3));
+        let step = Convex(|d| (d - 3).max(0) as i128);
+
+        for round in 0..200 {
+            let len = 1 + (next(40) + 20) as usize;
+            let spread = [4, 50, 1000][round % 3];
+            let crabs = (0..len).map(|_| next(spread)).collect::<Vec<i64>>();
+            assert_eq!(brute_force(&crabs, &Linear), align(&crabs, &Linear).1);
+            assert_eq!(
+                brute_force(&crabs, &Triangular),
+                align(&crabs, &Triangular).1
+            );
+            assert_eq!(brute_force(&crabs, &Quadratic), align(&crabs, &Quadratic).1);
+            assert_eq!(brute_force(&crabs, &cube), align(&crabs, &cube).1);
+            assert_eq!(brute_force(&crabs, &step), align(&crabs, &step).1);
+        }
+    }
+
+    #[test]
+    fn test_large_input() {
+        let crabs = (0..1_000_000i64)
+            .map(|i| (i * 7_919_993) % 1_000_000_007)
+            .collect::<Vec<i64>>();
+        let (at, fuel) = align(&crabs, &Triangular);
+        assert!(fuel < Triangular.total(&crabs, at - 1));
+        assert!(fuel <= Triangular.total(&crabs, at + 1));
+
+        let (at, fuel) = align(&crabs, &Convex(|d| d as i128 * d as i128));
+        assert_eq!(fuel, align(&crabs, &Quadratic).1);
+        assert!(fuel <= Quadratic.total(&crabs, at + 1));
+    }
+}
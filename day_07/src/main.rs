@@ -1,3 +1,9 @@
+mod fuel;
+mod plane;
+
+use fuel::{align, Convex, FuelCost, Linear, Quadratic, Triangular};
+use plane::Metric;
+use std::env;
 use std::fs;
 use std::time::Instant;
 
@@ -16,29 +22,11 @@ fn generate(input: &str) -> Generated {
 }
 
 fn part_1(input: &Generated) -> i64 {
-    let min = input.iter().min().unwrap();
-    let max = input.iter().max().unwrap();
-    (*min..*max)
-        .map(|alignment| input.iter().map(|crab| i64::abs(alignment - crab)).sum())
-        .min()
-        .unwrap()
+    align(input, &Linear).1 as i64
 }
 
 fn part_2(input: &Generated) -> i64 {
-    let min = input.iter().min().unwrap();
-    let max = input.iter().max().unwrap();
-    (*min..*max)
-        .map(|alignment| {
-            input
-                .iter()
-                .map(|crab| {
-                    let n = i64::abs(alignment - crab);
-                    (n * (n + 1)) / 2
-                })
-                .sum()
-        })
-        .min()
-        .unwrap()
+    align(input, &Triangular).1 as i64
 }
 
 fn main() {
@@ -64,6 +52,45 @@ fn main() {
         res2,
         res2_stop.duration_since(res2_start)
     );
+
+    // e.g. `--cost quadratic`, `--cost power:3`,
+    // `--plane fleet.txt --metric chebyshev`
+    let args = env::args().collect::<Vec<String>>();
+    let arg = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .map(|pos| args.get(pos + 1).expect("Missing argument value").as_str())
+    };
+    if let Some(name) = arg("--cost") {
+        let cost: Box<dyn FuelCost> = match name.split_once(':') {
+            Some(("power", k)) => {
+                let k = k.parse::<u32>().unwrap();
+                Box::new(Convex(move |d| (d as i128).pow(k)))
+            }
+            _ => match name {
+                "linear" => Box::new(Linear),
+                "triangular" => Box::new(Triangular),
+                "quadratic" => Box::new(Quadratic),
+                _ => panic!("Unknown cost: {}", name),
+            },
+        };
+        let (at, fuel) = align(&data, cost.as_ref());
+        println!("Cheapest {} alignment: {} using {} fuel", name, at, fuel);
+    }
+    if let Some(path) = arg("--plane") {
+        let content = fs::read_to_string(path).expect("file not found");
+        let crabs = plane::parse(&content).unwrap();
+        let metric = arg("--metric").map_or(Metric::Manhattan, |m| m.parse().unwrap());
+
+        let meeting = plane::meet(&crabs, metric);
+        println!(
+            "Meeting point {:?} using {} fuel",
+            meeting.point, meeting.total
+        );
+        for (crab, fuel) in crabs.iter().zip(meeting.contributions) {
+            println!("  {},{},{}: {}", crab.x, crab.y, crab.weight, fuel);
+        }
+    }
 }
 
 #[cfg(test)]
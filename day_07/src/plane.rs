@@ -0,0 +1,236 @@
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Crab {
+    pub x: i64,
+    pub y: i64,
+    pub weight: i64,
+}
+
+// One crab per line as `x,y` or `x,y,weight`, the weight defaulting to 1.
+pub fn parse(input: &str) -> Result<Vec<Crab>, String> {
+    let crabs = input
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .map(|l| {
+            let values = l
+                .split(',')
+                .map(|v| v.trim().parse::<i64>())
+                .collect::<Result<Vec<i64>, _>>()
+                .map_err(|_| format!("Incorrect crab: {}", l))?;
+            match values[..] {
+                [x, y] => Ok(Crab { x, y, weight: 1 }),
+                [x, y, weight] if weight >= 0 => Ok(Crab { x, y, weight }),
+                _ => Err(format!("Incorrect crab: {}", l)),
+            }
+        })
+        .collect::<Result<Vec<Crab>, String>>()?;
+    match crabs.is_empty() {
+        true => Err("No crabs".to_string()),
+        false => Ok(crabs),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metric {
+    Manhattan,
+    Chebyshev,
+    TriangularManhattan,
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "manhattan" => Ok(Metric::Manhattan),
+            "chebyshev" => Ok(Metric::Chebyshev),
+            "triangular" => Ok(Metric::TriangularManhattan),
+            _ => Err(format!("Unknown metric: {}", s)),
+        }
+    }
+}
+
+impl Metric {
+    pub fn cost(&self, crab: &Crab, at: (i64, i64)) -> i128 {
+        let dx = (crab.x - at.0).abs() as i128;
+        let dy = (crab.y - at.1).abs() as i128;
+        let fuel = match self {
+            Metric::Manhattan => dx + dy,
+            Metric::Chebyshev => dx.max(dy),
+            Metric::TriangularManhattan => (dx + dy) * (dx + dy + 1) / 2,
+        };
+        crab.weight as i128 * fuel
+    }
+
+    pub fn total(&self, crabs: &[Crab], at: (i64, i64)) -> i128 {
+        crabs.iter().map(|c| self.cost(c, at)).sum()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Meeting {
+    pub point: (i64, i64),
+    pub total: i128,
+    // Weighted fuel of every crab, in input order.
+    pub contributions: Vec<i128>,
+}
+
+fn weighted_median(mut values: Vec<(i64, i64)>) -> i64 {
+    values.sort_unstable();
+    let total = values.iter().map(|&(_, w)| w as i128).sum::<i128>();
+    let mut seen = 0;
+    for (value, weight) in values {
+        seen += weight as i128;
+        if 2 * seen >= total {
+            return value;
+        }
+    }
+    unreachable!()
+}
+
+fn ternary(mut lo: i64, mut hi: i64, f: impl Fn(i64) -> i128) -> i64 {
+    while hi - lo > 2 {
+        let m1 = lo + (hi - lo) / 3;
+        let m2 = hi - (hi - lo) / 3;
+        match f(m1).cmp(&f(m2)) {
+            std::cmp::Ordering::Less => hi = m2 - 1,
+            std::cmp::Ordering::Greater => lo = m1 + 1,
+            std::cmp::Ordering::Equal => (lo, hi) = (m1, m2),
+        }
+    }
+    (lo..=hi).min_by_key(|&v| f(v)).unwrap()
+}
+
+fn best_point(crabs: &[Crab], metric: Metric) -> (i64, i64) {
+    let median = |f: &dyn Fn(&Crab) -> i64| {
+        weighted_median(crabs.iter().map(|c| (f(c), c.weight)).collect())
+    };
+    match metric {
+        // Both axes are independent, each minimized by its weighted median.
+        Metric::Manhattan => (median(&|c| c.x), median(&|c| c.y)),
+        // Rotated by 45 degrees the distance is half the Manhattan one, but
+        // only points with `u` and `v` of the same parity map back to the grid,
+        // so the best one sits next to the medians.
+        Metric::Chebyshev => {
+            let (u, v) = (median(&|c| c.x + c.y), median(&|c| c.x - c.y));
+            (u - 1..=u + 1)
+                .flat_map(|u| (v - 1..=v + 1).map(move |v| (u, v)))
+                .filter(|(u, v)| (u - v) % 2 == 0)
+                .map(|(u, v)| ((u + v) / 2, (u - v) / 2))
+                .min_by_key(|&p| (metric.total(crabs, p), p))
+                .unwrap()
+        }
+        // The total is convex, so nest a ternary search per axis and polish
+        // the result with steps to cheaper neighbours.
+        Metric::TriangularManhattan => {
+            let (x_min, x_max) = (
+                crabs.iter().map(|c| c.x).min().unwrap(),
+                crabs.iter().map(|c| c.x).max().unwrap(),
+            );
+            let (y_min, y_max) = (
+                crabs.iter().map(|c| c.y).min().unwrap(),
+                crabs.iter().map(|c| c.y).max().unwrap(),
+            );
+            let best_y = |x: i64| ternary(y_min, y_max, |y| metric.total(crabs, (x, y)));
+            let x = ternary(x_min, x_max, |x| metric.total(crabs, (x, best_y(x))));
+            let mut point = (x, best_y(x));
+            let mut total = metric.total(crabs, point);
+            loop {
+                let next = (-1..=1)
+                    .flat_map(|dx| (-1..=1).map(move |dy| (point.0 + dx, point.1 + dy)))
+                    .map(|p| (metric.total(crabs, p), p))
+                    .min()
+                    .unwrap();
+                if next.0 >= total {
+                    break point;
+                }
+                (total, point) = next;
+            }
+        }
+    }
+}
+
+pub fn meet(crabs: &[Crab], metric: Metric) -> Meeting {
+    let point = best_point(crabs, metric);
+    let contributions = crabs
+        .iter()
+        .map(|c| metric.cost(c, point))
+        .collect::<Vec<i128>>();
+    Meeting {
+        point,
+        total: contributions.iter().sum(),
+        contributions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force(crabs: &[Crab], metric: Metric) -> i128 {
+        (-12..=12)
+            .flat_map(|x| (-12..=12).map(move |y| (x, y)))
+            .map(|p| metric.total(crabs, p))
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut seed = 3u64;
+        let mut next = |m: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % m) as i64
+        };
+
+        for _ in 0..300 {
+            let len = 1 + next(12) as usize;
+            let crabs = (0..len)
+                .map(|_| Crab {
+                    x: next(21) - 10,
+                    y: next(21) - 10,
+                    weight: next(5),
+                })
+                .collect::<Vec<Crab>>();
+            if crabs.iter().all(|c| c.weight == 0) {
+                continue;
+            }
+            for metric in [
+                Metric::Manhattan,
+                Metric::Chebyshev,
+                Metric::TriangularManhattan,
+            ] {
+                let meeting = meet(&crabs, metric);
+                assert_eq!(brute_force(&crabs, metric), meeting.total);
+                assert_eq!(metric.total(&crabs, meeting.point), meeting.total);
+            }
+        }
+    }
+
+    #[test]
+    fn test_meeting() {
+        let crabs = parse("0,0\n4,0,3\n0,2\n").unwrap();
+        assert_eq!(
+            Meeting {
+                point: (4, 0),
+                total: 10,
+                contributions: vec![4, 0, 6],
+            },
+            meet(&crabs, Metric::Manhattan)
+        );
+
+        let meeting = meet(&crabs, Metric::Chebyshev);
+        assert_eq!(8, meeting.total);
+        assert_eq!(meeting.total, meeting.contributions.iter().sum::<i128>());
+
+        assert!(parse("1,2,3,4").is_err());
+        assert!(parse("1,2,-1").is_err());
+        assert!(parse("1").is_err());
+        assert!(parse("").is_err());
+        assert!(parse("\n  \n").is_err());
+    }
+}
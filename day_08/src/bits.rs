@@ -0,0 +1,105 @@
+// Seven segment patterns as bitmasks, bit 0 being wire (or segment) `a`.
+pub type Pattern = u8;
+
+const DIGIT_SEGMENTS: [Pattern; 10] = [
+    0b1110111, 0b0100100, 0b1011101, 0b1101101, 0b0101110, 0b1101011, 0b1111011, 0b0100101,
+    0b1111111, 0b1101111,
+];
+
+// The digit shown by every set of lit segments, or `NONE`.
+pub const NONE: u8 = u8::MAX;
+pub const DIGITS: [u8; 128] = {
+    let mut table = [NONE; 128];
+    let mut digit = 0;
+    while digit < 10 {
+        table[DIGIT_SEGMENTS[digit] as usize] = digit as u8;
+        digit += 1;
+    }
+    table
+};
+
+// None for anything but the wires `a` to `g`.
+pub fn pattern(s: &str) -> Option<Pattern> {
+    s.bytes().try_fold(0, |p, b| match b.checked_sub(b'a') {
+        Some(w) if w < 7 => Some(p | 1 << w),
+        _ => None,
+    })
+}
+
+// Across the ten digits segments b, e and f are lit a unique number of
+// times (6, 4 and 9). Of the two lit 8 times only c is part of 1, and of the
+// two lit 7 times only d is part of 4.
+pub fn mapping(signals: &[Pattern]) -> Option<[Pattern; 7]> {
+    let one = *signals.iter().find(|p| p.count_ones() == 2)?;
+    let four = *signals.iter().find(|p| p.count_ones() == 4)?;
+
+    let mut map = [0; 7];
+    for (wire, segment) in map.iter_mut().enumerate() {
+        let bit = 1 << wire;
+        let count = signals.iter().filter(|&&p| p & bit != 0).count();
+        *segment = match (count, one & bit != 0, four & bit != 0) {
+            (8, false, _) => 1 << 0,
+            (6, _, _) => 1 << 1,
+            (8, true, _) => 1 << 2,
+            (7, _, true) => 1 << 3,
+            (4, _, _) => 1 << 4,
+            (9, _, _) => 1 << 5,
+            (7, _, false) => 1 << 6,
+            _ => return None,
+        };
+    }
+    match map.iter().fold(0, |all, s| all | s) {
+        0b1111111 => Some(map),
+        _ => None,
+    }
+}
+
+pub fn digit(map: &[Pattern; 7], scrambled: Pattern) -> Option<u8> {
+    let lit = (0..7)
+        .filter(|w| scrambled & 1 << w != 0)
+        .fold(0, |lit, w| lit | map[w]);
+    match DIGITS[lit as usize] {
+        NONE => None,
+        d => Some(d),
+    }
+}
+
+// The four digit number of a display, if its signals are the ten digits.
+pub fn read(signals: &[Pattern], outputs: &[Pattern]) -> Option<i32> {
+    let map = mapping(signals)?;
+    outputs
+        .iter()
+        .try_fold(0, |n, &o| Some(n * 10 + digit(&map, o)? as i32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::Glyphs;
+
+    #[test]
+    fn test_lookup_table() {
+        let glyphs = Glyphs::seven();
+        for lit in 0..128u32 {
+            let expected = glyphs.lookup(lit).map(|c| c.to_digit(10).unwrap() as u8);
+            assert_eq!(expected.unwrap_or(NONE), DIGITS[lit as usize]);
+        }
+    }
+
+    #[test]
+    fn test_read() {
+        let signals = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab"
+            .split_whitespace()
+            .map(|s| pattern(s).unwrap())
+            .collect::<Vec<Pattern>>();
+        let outputs = "cdfeb fcadb cdfeb cdbaf"
+            .split_whitespace()
+            .map(|s| pattern(s).unwrap())
+            .collect::<Vec<Pattern>>();
+        assert_eq!(Some(5353), read(&signals, &outputs));
+        assert_eq!(None, read(&signals[1..], &outputs));
+        assert_eq!(None, read(&signals, &[pattern("ef").unwrap()]));
+        assert_eq!(None, pattern("abh"));
+        assert_eq!(None, pattern("Ab"));
+    }
+}
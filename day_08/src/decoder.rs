@@ -0,0 +1,320 @@
+// Segments are named by letters from `a`, bit 0 being `a`. Wires use the same
+// letters, and a mapping sends every wire to the segment it really drives.
+pub type Mapping = Vec<usize>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Glyphs {
+    pub segments: usize,
+    pub glyphs: Vec<(char, u32)>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DecodeError {
+    // The first two mappings found, out of possibly many more.
+    Ambiguous(Mapping, Mapping),
+    Impossible,
+    UnknownWire(char),
+}
+
+fn table(segments: usize, glyphs: &[(char, &str)]) -> Glyphs {
+    Glyphs {
+        segments,
+        glyphs: glyphs
+            .iter()
+            .map(|&(label, lit)| (label, lit.bytes().fold(0, |m, b| m | 1 << (b - b'a'))))
+            .collect(),
+    }
+}
+
+impl Glyphs {
+    pub fn seven() -> Glyphs {
+        table(
+            7,
+            &[
+                ('0', "abcefg"),
+                ('1', "cf"),
+                ('2', "acdeg"),
+                ('3', "acdfg"),
+                ('4', "bcdf"),
+                ('5', "abdfg"),
+                ('6', "abdefg"),
+                ('7', "acf"),
+                ('8', "abcdefg"),
+                ('9', "abcdfg"),
+            ],
+        )
+    }
+
+    // a-f as on seven segments, g/h the middle halves, i/k/l/n the diagonals
+    // (upper left, upper right, lower left, lower right) and j/m the centre
+    // verticals. A few letters are included so that every segment is lit by
+    // some glyph.
+    pub fn fourteen() -> Glyphs {
+        table(
+            14,
+            &[
+                ('0', "abcdefkl"),
+                ('1', "bc"),
+                ('2', "abdegh"),
+                ('3', "abcdh"),
+                ('4', "bcfgh"),
+                ('5', "acdfgh"),
+                ('6', "acdefgh"),
+                ('7', "abc"),
+                ('8', "abcdefgh"),
+                ('9', "abcdfgh"),
+                ('K', "efgkn"),
+                ('M', "bcefik"),
+                ('T', "ajm"),
+                ('X', "ikln"),
+                ('Y', "ikm"),
+                ('Z', "adkl"),
+            ],
+        )
+    }
+
+    // Fourteen segments with the top and bottom bars split: a/b top, c/d
+    // right, e/f bottom (right, left), g/h left, i/j middle, k/m/n/p the
+    // diagonals and l/o the centre verticals.
+    pub fn sixteen() -> Glyphs {
+        table(
+            16,
+            &[
+                ('0', "abcdefghmn"),
+                ('1', "cd"),
+                ('2', "abcefgij"),
+                ('3', "abcdefj"),
+                ('4', "cdhij"),
+                ('5', "abdefhij"),
+                ('6', "abdefghij"),
+                ('7', "abcd"),
+                ('8', "abcdefghij"),
+                ('9', "abcdefhij"),
+                ('K', "ghimp"),
+                ('M', "cdghkm"),
+                ('T', "ablo"),
+                ('X', "kmnp"),
+                ('Y', "kmo"),
+                ('[', "afgh"),
+                (']', "bcde"),
+            ],
+        )
+    }
+
+    // One glyph per line as `<label> <lit segments>`, e.g. `1 cf`.
+    pub fn parse(input: &str) -> Result<Glyphs, String> {
+        let glyphs = input
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .map(|l| match l.split_whitespace().collect::<Vec<&str>>()[..] {
+                [label, lit] if label.chars().count() == 1 => {
+                    let mask = mask(lit, 26).map_err(|_| format!("Incorrect glyph: {}", l))?;
+                    Ok((label.chars().next().unwrap(), mask))
+                }
+                _ => Err(format!("Incorrect glyph: {}", l)),
+            })
+            .collect::<Result<Vec<(char, u32)>, String>>()?;
+        let segments = glyphs.iter().map(|(_, m)| 32 - m.leading_zeros()).max();
+        Ok(Glyphs {
+            segments: segments.unwrap_or(0) as usize,
+            glyphs,
+        })
+    }
+
+    pub fn lookup(&self, lit: u32) -> Option<char> {
+        self.glyphs
+            .iter()
+            .find(|&&(_, m)| m == lit)
+            .map(|&(label, _)| label)
+    }
+}
+
+pub fn mask(pattern: &str, wires: usize) -> Result<u32, DecodeError> {
+    pattern
+        .chars()
+        .try_fold(0, |m, c| match (c as u32).checked_sub('a' as u32) {
+            Some(w) if (w as usize) < wires => Ok(m | 1 << w),
+            _ => Err(DecodeError::UnknownWire(c)),
+        })
+}
+
+// The segments lit by a scrambled pattern under the mapping.
+pub fn apply(mapping: &Mapping, pattern: u32) -> u32 {
+    (0..mapping.len())
+        .filter(|&w| pattern & 1 << w != 0)
+        .fold(0, |m, w| m | 1 << mapping[w])
+}
+
+struct Search<'a> {
+    // Every pattern with the glyphs of its size.
+    patterns: Vec<(u32, Vec<u32>)>,
+    candidates: Vec<u32>,
+    assigned: Vec<Option<usize>>,
+    found: Vec<Mapping>,
+    glyphs: &'a Glyphs,
+}
+
+impl Search<'_> {
+    // A partial mapping is kept while every pattern can still become a glyph
+    // containing the segments of its assigned wires and none of the others'.
+    fn consistent(&self) -> bool {
+        self.patterns.iter().all(|(pattern, glyphs)| {
+            let (mut lit, mut dark) = (0, 0);
+            for (w, s) in self.assigned.iter().enumerate() {
+                match s {
+                    Some(s) if pattern & 1 << w != 0 => lit |= 1 << s,
+                    Some(s) => dark |= 1 << s,
+                    None => (),
+                }
+            }
+            glyphs.iter().any(|g| g & lit == lit && g & dark == 0)
+        })
+    }
+
+    fn search(&mut self, used: u32) {
+        let open = (0..self.glyphs.segments)
+            .filter(|&w| self.assigned[w].is_none())
+            .min_by_key(|&w| (self.candidates[w] & !used).count_ones());
+        let wire = match open {
+            Some(wire) => wire,
+            None => {
+                let mapping = self.assigned.iter().map(|s| s.unwrap()).collect();
+                return self.found.push(mapping);
+            }
+        };
+
+        for segment in 0..self.glyphs.segments {
+            if self.candidates[wire] & !used & 1 << segment == 0 {
+                continue;
+            }
+            self.assigned[wire] = Some(segment);
+            if self.consistent() {
+                self.search(used | 1 << segment);
+            }
+            self.assigned[wire] = None;
+            if self.found.len() > 1 {
+                return;
+            }
+        }
+    }
+}
+
+// Finds the wire permutation under which every pattern shows a glyph of the
+// table. Pattern sizes narrow down each wire's segments first, and a
+// backtracking search over the most constrained wire does the rest, stopping
+// as soon as a second mapping shows the patterns can't tell them apart.
+pub fn decode(glyphs: &Glyphs, patterns: &[&str]) -> Result<Mapping, DecodeError> {
+    let wires = glyphs.segments;
+    let all = (1u32 << wires) - 1;
+    let mut candidates = vec![all; wires];
+
+    let mut sized = Vec::new();
+    for pattern in patterns {
+        let pattern = mask(pattern, wires)?;
+        let same_size = glyphs
+            .glyphs
+            .iter()
+            .map(|&(_, m)| m)
+            .filter(|m| m.count_ones() == pattern.count_ones())
+            .collect::<Vec<u32>>();
+        let any = same_size.iter().fold(0, |a, m| a | m);
+        let every = same_size.iter().fold(all, |a, m| a & m);
+        for (w, c) in candidates.iter_mut().enumerate() {
+            match pattern & 1 << w != 0 {
+                true => *c &= any,
+                false => *c &= !every,
+            }
+        }
+        sized.push((pattern, same_size));
+    }
+    sized.sort_unstable();
+    sized.dedup();
+
+    let mut search = Search {
+        patterns: sized,
+        candidates,
+        assigned: vec![None; wires],
+        found: Vec::new(),
+        glyphs,
+    };
+    search.search(0);
+
+    let mut found = search.found.into_iter();
+    match (found.next(), found.next()) {
+        (Some(mapping), None) => Ok(mapping),
+        (Some(a), Some(b)) => Err(DecodeError::Ambiguous(a, b)),
+        _ => Err(DecodeError::Impossible),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    fn scramble(glyphs: &Glyphs, wiring: &[usize]) -> Vec<String> {
+        glyphs
+            .glyphs
+            .iter()
+            .map(|&(_, m)| {
+                (0..glyphs.segments)
+                    .filter(|&w| m & 1 << wiring[w] != 0)
+                    .map(|w| (b'a' + w as u8) as char)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decodes_every_table() {
+        let mut rng = StdRng::seed_from_u64(17);
+
+        for glyphs in [Glyphs::seven(), Glyphs::fourteen(), Glyphs::sixteen()] {
+            for _ in 0..20 {
+                let mut wiring = (0..glyphs.segments).collect::<Vec<usize>>();
+                wiring.shuffle(&mut rng);
+                let patterns = scramble(&glyphs, &wiring);
+                let patterns = patterns.iter().map(|p| p.as_str()).collect::<Vec<&str>>();
+                assert_eq!(Ok(wiring), decode(&glyphs, &patterns));
+            }
+        }
+    }
+
+    #[test]
+    fn test_ambiguous_and_impossible() {
+        let seven = Glyphs::seven();
+        assert!(matches!(
+            decode(&seven, &["ab", "abd", "abcdefg"]),
+            Err(DecodeError::Ambiguous(_, _))
+        ));
+        assert_eq!(Err(DecodeError::Impossible), decode(&seven, &["ab", "de"]));
+        assert_eq!(
+            Err(DecodeError::Impossible),
+            decode(&seven, &["abcde", "abcdf", "abcdg", "abcef"])
+        );
+        assert_eq!(Err(DecodeError::UnknownWire('h')), decode(&seven, &["ah"]));
+
+        // Missing digits are fine as long as the rest pins every wire down.
+        let mapping = decode(&seven, &["cf", "acf", "bcdf", "acdeg", "abdfg"]).unwrap();
+        assert_eq!((0..7).collect::<Mapping>(), mapping);
+    }
+
+    #[test]
+    fn test_custom_table() {
+        let glyphs = Glyphs::parse("L ab\nI a\nA abc\n").unwrap();
+        assert_eq!(3, glyphs.segments);
+        assert_eq!(Ok(vec![2, 1, 0]), decode(&glyphs, &["bc", "c", "abc"]));
+
+        // Mirrored glyphs can't tell the outer wires apart.
+        let glyphs = Glyphs::parse("L ab\nR bc\nA abc").unwrap();
+        assert!(matches!(
+            decode(&glyphs, &["ab", "bc", "abc"]),
+            Err(DecodeError::Ambiguous(_, _))
+        ));
+        assert!(Glyphs::parse("L ab c").is_err());
+        assert!(Glyphs::parse("L aB").is_err());
+    }
+}
@@ -1,10 +1,16 @@
-use std::collections::HashMap;
+mod bits;
+mod decoder;
+mod scramble;
+
+use decoder::Glyphs;
+use std::env;
 use std::fs;
+use std::hint;
 use std::time::Instant;
 
 type Generated<'a> = Vec<(Vec<&'a str>, Vec<&'a str>)>;
 
-fn generate(input: &str) -> Generated {
+fn generate(input: &str) -> Generated<'_> {
     input
         .lines()
         .map(|l| l.trim())
@@ -29,128 +35,67 @@ fn part_1(input: &Generated) -> i32 {
     })
 }
 
-fn get_wire_mapping(data: &Vec<&str>) -> HashMap<char, char> {
-    let mut map = HashMap::<char, char>::new();
-
-    let one = data.iter().find(|v| v.len() == 2).expect("1 not found");
-    let four = data.iter().find(|v| v.len() == 4).expect("4 not found");
-    let seven = data.iter().find(|v| v.len() == 3).expect("7 not found");
-    let eight = data.iter().find(|v| v.len() == 7).expect("8 not found");
-
-    let segment_a = seven
-        .chars()
-        .find(|&c| !one.contains(c))
-        .expect("A not found");
-    map.insert(segment_a, 'a');
-
-    //8-4-7 = eg
-    let segment_eg = eight
-        .chars()
-        .filter(|&c| !four.contains(c) && !seven.contains(c))
-        .collect::<Vec<char>>();
-
-    let with_len_5 = data.iter().filter(|v| v.len() == 5).collect::<Vec<&&str>>();
-    let two = with_len_5
+// Decodes the outputs of one display with the given glyph table, reading
+// the labels of the shown glyphs.
+fn read_display(
+    signals: &[&str],
+    outputs: &[&str],
+    glyphs: &Glyphs,
+) -> Result<String, decoder::DecodeError> {
+    let patterns = signals
         .iter()
-        .find(|v| v.contains(segment_eg[0]) && v.contains(segment_eg[1]))
-        .expect("2 not found");
-    let three_five = with_len_5
+        .chain(outputs)
+        .cloned()
+        .collect::<Vec<&str>>();
+    let mapping = decoder::decode(glyphs, &patterns)?;
+    outputs
         .iter()
-        .filter(|&item| item != two)
-        .collect::<Vec<&&&str>>();
-
-    //7-eg = d
-    let segment_d = two
-        .chars()
-        .find(|&c| !seven.contains(c) && !segment_eg.contains(&c))
-        .expect("D not found");
-    map.insert(segment_d, 'd');
-
-    //8-7-eg-d = b
-    let segment_b = eight
-        .chars()
-        .find(|&c| !seven.contains(c) && !segment_eg.contains(&c) && c != segment_d)
-        .expect("B not found");
-    map.insert(segment_b, 'b');
-
-    //4-2-b = f
-    let segment_f = four
-        .chars()
-        .find(|&c| !two.contains(c) && c != segment_b)
-        .expect("F not found");
-    map.insert(segment_f, 'f');
-
-    //4-a-d-f = c
-    let segment_c = four
-        .chars()
-        .find(|&c| c != segment_b && c != segment_d && c != segment_f)
-        .expect("C not found");
-    map.insert(segment_c, 'c');
-
-    //2-3/5-c = e
-    let segment_e = two
-        .chars()
-        .find(|&c| !three_five[0].contains(c) && c != segment_c)
-        .expect("E not found");
-    map.insert(segment_e, 'e');
-
-    //8-a-b-c-d-e-f = g
-    let segment_g = eight
-        .chars()
-        .find(|&c| {
-            c != segment_a
-                && c != segment_b
-                && c != segment_c
-                && c != segment_d
-                && c != segment_e
-                && c != segment_f
+        .map(|o| {
+            let lit = decoder::apply(&mapping, decoder::mask(o, glyphs.segments)?);
+            Ok(glyphs.lookup(lit).expect("Incorrect str to digit mapping"))
         })
-        .expect("G not found");
-    map.insert(segment_g, 'g');
+        .collect()
+}
 
-    map
+fn read_outputs(input: &Generated, glyphs: &Glyphs) -> Vec<Result<String, decoder::DecodeError>> {
+    input
+        .iter()
+        .map(|(signals, outputs)| read_display(signals, outputs, glyphs))
+        .collect()
 }
 
+// The bitmask mapping needs all ten digits among the signals and only wires
+// `a` to `g`, so other displays go through the generic decoder instead.
 fn part_2(input: &Generated) -> i32 {
-    let str_to_digit: HashMap<&str, i32> = [
-        ("abcefg", 0),
-        ("cf", 1),
-        ("acdeg", 2),
-        ("acdfg", 3),
-        ("bcdf", 4),
-        ("abdfg", 5),
-        ("abdefg", 6),
-        ("acf", 7),
-        ("abcdefg", 8),
-        ("abcdfg", 9),
-    ]
-    .into();
-
     input
         .iter()
-        .map(|l| {
-            let map = get_wire_mapping(&l.0);
-
-            let mut mapped =
-                l.1.iter()
-                    .map(|item| {
-                        item.chars()
-                            .map(|c| *map.get(&c).expect("Incorrect wire to wire"))
-                            .collect::<Vec<char>>()
-                    })
-                    .collect::<Vec<Vec<char>>>();
-            mapped.iter_mut().for_each(|item| item.sort_unstable());
-            mapped
+        .map(|(signals, outputs)| {
+            let patterns = signals
+                .iter()
+                .map(|s| bits::pattern(s))
+                .collect::<Option<Vec<_>>>();
+            let shown = outputs
                 .iter()
-                .map(|item| {
-                    let s = String::from_iter(item.iter());
-                    *str_to_digit
-                        .get(s.as_str())
-                        .expect("Incorrect str to digit mapping")
+                .map(|s| bits::pattern(s))
+                .collect::<Option<Vec<_>>>();
+            patterns
+                .zip(shown)
+                .and_then(|(patterns, shown)| bits::read(&patterns, &shown))
+                .unwrap_or_else(|| {
+                    read_display(signals, outputs, &Glyphs::seven())
+                        .expect("Incorrect wire mapping")
+                        .parse()
+                        .unwrap()
                 })
-                .collect::<Vec<i32>>()
         })
-        .map(|item| item[0] * 1000 + item[1] * 100 + item[2] * 10 + item[3])
+        .sum()
+}
+
+// Part 2 through the generic decoder, kept as the reference for `--bench`.
+fn part_2_glyphs(input: &Generated) -> i32 {
+    read_outputs(input, &Glyphs::seven())
+        .into_iter()
+        .map(|digits| digits.unwrap().parse::<i32>().unwrap())
         .sum()
 }
 
@@ -177,6 +122,61 @@ fn main() {
         res2,
         res2_stop.duration_since(res2_start)
     );
+
+    // e.g. `--glyphs fourteen`, `--glyphs font.txt` with `<label> <segments>`
+    // lines, `--bench 1000`, `--scramble 5353 --seed 1 --corrupt 0.1` printing
+    // the lines and their ground truth wiring to stderr
+    let args = env::args().collect::<Vec<String>>();
+    if let Some(pos) = args.iter().position(|a| a == "--bench") {
+        let runs = args
+            .get(pos + 1)
+            .expect("Missing argument value")
+            .parse::<u32>()
+            .unwrap();
+        for (name, solve) in [
+            ("bitmask", part_2 as fn(&Generated) -> i32),
+            ("glyph decoder", part_2_glyphs),
+        ] {
+            let start = Instant::now();
+            let mut result = 0;
+            for _ in 0..runs {
+                result = solve(hint::black_box(&data));
+            }
+            let stop = Instant::now();
+            println!(
+                "{}: {} in {:?} per run",
+                name,
+                result,
+                stop.duration_since(start) / runs
+            );
+        }
+    }
+    let arg = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .map(|pos| args.get(pos + 1).expect("Missing argument value").as_str())
+    };
+    let glyphs = match arg("--glyphs") {
+        None | Some("seven") => Glyphs::seven(),
+        Some("fourteen") => Glyphs::fourteen(),
+        Some("sixteen") => Glyphs::sixteen(),
+        Some(path) => Glyphs::parse(&fs::read_to_string(path).expect("file not found")).unwrap(),
+    };
+    if let Some(message) = arg("--scramble") {
+        let seed = arg("--seed").map_or(0, |v| v.parse().unwrap());
+        let corruption = arg("--corrupt").map_or(0.0, |v| v.parse().unwrap());
+        for scrambled in scramble::scramble(&glyphs, message, 4, seed, corruption).unwrap() {
+            println!("{}", scrambled.line);
+            eprintln!("{:?} {:?}", scrambled.mapping, scrambled.corruption);
+        }
+    } else if arg("--glyphs").is_some() {
+        for (i, shown) in read_outputs(&data, &glyphs).into_iter().enumerate() {
+            match shown {
+                Ok(shown) => println!("{}: {}", i + 1, shown),
+                Err(e) => println!("{}: {:?}", i + 1, e),
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -208,4 +208,27 @@ mod tests {
         egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
         gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce")));
     }
+    #[test]
+    fn test_part_2_missing_digit() {
+        // The 3 only shows up among the outputs.
+        assert_eq!(
+            8394,
+            part_2(&generate(
+                "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fabcd edb | fdgacbe cefdb cefbgd gcbe"
+            ))
+        );
+    }
+    #[test]
+    fn test_part_2_glyphs() {
+        assert_eq!(61229, part_2_glyphs(&generate("be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe
+        edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc
+        fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef | cg cg fdcagb cbg
+        fbegcd cbd adcefb dageb afcb bc aefdc ecdab fgdeca fcdbega | efabcd cedba gadfec cb
+        aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea
+        fgeab ca afcebg bdacfeg cfaedg gcfdb baec bfadeg bafgc acf | gebdcfa ecba ca fadegcb
+        dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbcadfe
+        bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
+        egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
+        gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce")));
+    }
 }
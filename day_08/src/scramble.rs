@@ -0,0 +1,195 @@
+use crate::decoder::{Glyphs, Mapping};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Corruption {
+    // A wire toggled in one signal pattern, so that it shows no glyph.
+    FlippedWire { pattern: usize, wire: usize },
+    DroppedPattern(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scrambled {
+    pub line: String,
+    // Wire to segment, as `decoder::decode` reports it.
+    pub mapping: Mapping,
+    pub corruption: Option<Corruption>,
+}
+
+fn letters(wires: u32, segments: usize, rng: &mut StdRng) -> String {
+    let mut letters = (0..segments)
+        .filter(|&w| wires & 1 << w != 0)
+        .map(|w| (b'a' + w as u8) as char)
+        .collect::<Vec<char>>();
+    letters.shuffle(rng);
+    letters.into_iter().collect()
+}
+
+// Splits the message into displays of `width` glyphs, each wired at random
+// and showing every glyph of the table once among its signal patterns. With
+// probability `corruption` a display gets one defect.
+pub fn scramble(
+    glyphs: &Glyphs,
+    message: &str,
+    width: usize,
+    seed: u64,
+    corruption: f64,
+) -> Result<Vec<Scrambled>, String> {
+    if !(0.0..=1.0).contains(&corruption) {
+        return Err(format!("Corruption not within 0 and 1: {}", corruption));
+    }
+    let message = message
+        .chars()
+        .map(|c| {
+            glyphs
+                .glyphs
+                .iter()
+                .find(|&&(label, _)| label == c)
+                .map(|&(_, lit)| lit)
+                .ok_or(format!("No glyph for {:?}", c))
+        })
+        .collect::<Result<Vec<u32>, String>>()?;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let segments = glyphs.segments;
+    let mut lines = Vec::new();
+    for display in message.chunks(width.max(1)) {
+        let mut mapping = (0..segments).collect::<Mapping>();
+        mapping.shuffle(&mut rng);
+        // The wires driving a set of lit segments, and back.
+        let wire_of = |lit: u32| {
+            (0..segments)
+                .filter(|&w| lit & 1 << mapping[w] != 0)
+                .fold(0, |wires, w| wires | 1 << w)
+        };
+        let lit_by = |wires: u32| {
+            (0..segments)
+                .filter(|&w| wires & 1 << w != 0)
+                .fold(0, |lit, w| lit | 1 << mapping[w])
+        };
+
+        let mut signals = glyphs
+            .glyphs
+            .iter()
+            .map(|&(_, lit)| wire_of(lit))
+            .collect::<Vec<u32>>();
+        signals.shuffle(&mut rng);
+
+        let mut defect = None;
+        if rng.gen_bool(corruption) {
+            defect = match rng.gen_bool(0.5) {
+                true => {
+                    let shown = glyphs
+                        .glyphs
+                        .iter()
+                        .map(|&(_, lit)| lit)
+                        .collect::<Vec<_>>();
+                    let choices = (0..signals.len())
+                        .flat_map(|p| (0..segments).map(move |w| (p, w)))
+                        .filter(|&(p, w)| {
+                            let lit = lit_by(signals[p] ^ 1 << w);
+                            lit != 0 && !shown.contains(&lit)
+                        })
+                        .collect::<Vec<(usize, usize)>>();
+                    choices.choose(&mut rng).map(|&(pattern, wire)| {
+                        signals[pattern] ^= 1 << wire;
+                        Corruption::FlippedWire { pattern, wire }
+                    })
+                }
+                false => {
+                    let pattern = rng.gen_range(0..signals.len());
+                    signals.remove(pattern);
+                    Some(Corruption::DroppedPattern(pattern))
+                }
+            };
+        }
+
+        let signals = signals
+            .iter()
+            .map(|&s| letters(s, segments, &mut rng))
+            .collect::<Vec<String>>();
+        let outputs = display
+            .iter()
+            .map(|&lit| letters(wire_of(lit), segments, &mut rng))
+            .collect::<Vec<String>>();
+        lines.push(Scrambled {
+            line: format!("{} | {}", signals.join(" "), outputs.join(" ")),
+            mapping,
+            corruption: defect,
+        });
+    }
+
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::{decode, DecodeError};
+    use crate::{generate, part_2, read_outputs};
+
+    #[test]
+    fn test_round_trip() {
+        for glyphs in [Glyphs::seven(), Glyphs::fourteen(), Glyphs::sixteen()] {
+            let message = glyphs.glyphs.iter().map(|&(l, _)| l).collect::<String>();
+            let lines = scramble(&glyphs, &message, 4, 1, 0.0).unwrap();
+            let input = lines.iter().map(|l| l.line.as_str()).collect::<Vec<_>>();
+            let input = input.join("\n");
+            let data = generate(&input);
+
+            for (scrambled, (signals, outputs)) in lines.iter().zip(&data) {
+                let patterns = signals.iter().chain(outputs).cloned().collect::<Vec<_>>();
+                assert_eq!(Ok(scrambled.mapping.clone()), decode(&glyphs, &patterns));
+            }
+            let shown = read_outputs(&data, &glyphs)
+                .into_iter()
+                .map(|s| s.unwrap())
+                .collect::<String>();
+            assert_eq!(message, shown);
+        }
+
+        let lines = scramble(&Glyphs::seven(), "53531337", 4, 9, 0.0).unwrap();
+        let input = lines.iter().map(|l| l.line.clone()).collect::<Vec<_>>();
+        assert_eq!(5353 + 1337, part_2(&generate(&input.join("\n"))));
+        assert_ne!(
+            lines,
+            scramble(&Glyphs::seven(), "53531337", 4, 10, 0.0).unwrap()
+        );
+        assert!(scramble(&Glyphs::seven(), "12a", 4, 9, 0.0).is_err());
+        assert!(scramble(&Glyphs::seven(), "12", 4, 9, 1.5).is_err());
+        assert!(scramble(&Glyphs::seven(), "12", 4, 9, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_corruption() {
+        let glyphs = Glyphs::fourteen();
+        let lines = scramble(&glyphs, &"0123456789KMTXYZ".repeat(8), 4, 3, 1.0).unwrap();
+        let mut kinds = (0, 0);
+        for scrambled in lines {
+            let data = generate(&scrambled.line);
+            let patterns = data[0]
+                .0
+                .iter()
+                .chain(&data[0].1)
+                .cloned()
+                .collect::<Vec<_>>();
+            match scrambled.corruption.unwrap() {
+                // The true wiring shows no glyph there, and no other fits.
+                Corruption::FlippedWire { .. } => {
+                    kinds.0 += 1;
+                    assert_eq!(Err(DecodeError::Impossible), decode(&glyphs, &patterns));
+                }
+                Corruption::DroppedPattern(_) => {
+                    kinds.1 += 1;
+                    assert_eq!(glyphs.glyphs.len() - 1, data[0].0.len());
+                    if let Ok(mapping) = decode(&glyphs, &patterns) {
+                        assert_eq!(scrambled.mapping, mapping);
+                    }
+                }
+            }
+        }
+        assert!(kinds.0 > 0 && kinds.1 > 0);
+    }
+}
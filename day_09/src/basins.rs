@@ -0,0 +1,297 @@
+use crate::{Connectivity, Heightmap};
+
+pub const WALL: u32 = u32::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Options {
+    // Cells at least this high separate basins.
+    pub wall: u8,
+    pub connectivity: Connectivity,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            wall: 9,
+            connectivity: Connectivity::Four,
+        }
+    }
+}
+
+pub struct BasinMap {
+    // Basin of every cell, or `WALL`.
+    pub labels: Vec<u32>,
+    pub sizes: Vec<usize>,
+    pub options: Options,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Basin {
+    pub area: usize,
+    // Cell edges facing another basin, a wall or the map's border.
+    pub perimeter: usize,
+    // Top left and bottom right corners, as `(x, y)`.
+    pub bbox: ((usize, usize), (usize, usize)),
+    // The first lowest cell in reading order.
+    pub low_point: (usize, usize),
+    // Lowest wall touching the basin, where water would spill over.
+    pub rim: u8,
+    pub depth: u8,
+    pub volume: usize,
+}
+
+pub fn find(parent: &mut [u32], mut i: u32) -> u32 {
+    while parent[i as usize] != i {
+        parent[i as usize] = parent[parent[i as usize] as usize];
+        i = parent[i as usize];
+    }
+    i
+}
+
+// Union-find over one pass in index order, joining every open cell with the
+// already visited open neighbours, those with a smaller index. Roots always
+// stay the smallest index of their set, so a second pass in the same order
+// turns parents into basin ids within the same array. Returns the label of
+// every cell, or `WALL`, and the basin sizes. Shared by every grid shape.
+pub fn label<N, I>(
+    len: usize,
+    is_wall: impl Fn(usize) -> bool,
+    neighbours: N,
+) -> (Vec<u32>, Vec<usize>)
+where
+    N: Fn(usize) -> I,
+    I: Iterator<Item = usize>,
+{
+    assert!(len < WALL as usize, "Heightmap too large");
+    let mut labels = (0..len as u32).collect::<Vec<u32>>();
+
+    for i in 0..len {
+        if is_wall(i) {
+            labels[i] = WALL;
+            continue;
+        }
+        for n in neighbours(i) {
+            if n > i || labels[n] == WALL {
+                continue;
+            }
+            let (a, b) = (find(&mut labels, n as u32), find(&mut labels, i as u32));
+            labels[a.max(b) as usize] = a.min(b);
+        }
+    }
+
+    let mut sizes = Vec::new();
+    for i in 0..labels.len() {
+        let parent = labels[i];
+        if parent == WALL {
+            continue;
+        }
+        let label = match parent as usize == i {
+            true => {
+                sizes.push(0);
+                sizes.len() as u32 - 1
+            }
+            false => labels[parent as usize],
+        };
+        labels[i] = label;
+        sizes[label as usize] += 1;
+    }
+
+    (labels, sizes)
+}
+
+impl BasinMap {
+    pub fn new(map: &Heightmap, options: Options) -> BasinMap {
+        let (labels, sizes) = label(
+            map.cells.len(),
+            |i| map.cells[i] >= options.wall,
+            |i| map.neighbours(i, options.connectivity),
+        );
+        BasinMap {
+            labels,
+            sizes,
+            options,
+        }
+    }
+
+    // The map edge holds water, so a basin without walls around fills up to
+    // the wall height.
+    pub fn describe(&self, map: &Heightmap) -> Vec<Basin> {
+        let mut basins = vec![
+            Basin {
+                area: 0,
+                perimeter: 0,
+                bbox: ((usize::MAX, usize::MAX), (0, 0)),
+                low_point: (0, 0),
+                rim: self.options.wall,
+                depth: 0,
+                volume: 0,
+            };
+            self.sizes.len()
+        ];
+        let mut lowest = vec![u8::MAX; self.sizes.len()];
+        let mut rims = vec![None; self.sizes.len()];
+        let mut heights = vec![0; self.sizes.len()];
+
+        for (i, &label) in self.labels.iter().enumerate() {
+            if label == WALL {
+                continue;
+            }
+            let l = label as usize;
+            let (x, y) = (i % map.width, i / map.width);
+            let basin = &mut basins[l];
+            basin.area += 1;
+            basin.bbox = (
+                (basin.bbox.0 .0.min(x), basin.bbox.0 .1.min(y)),
+                (basin.bbox.1 .0.max(x), basin.bbox.1 .1.max(y)),
+            );
+            if map.cells[i] < lowest[l] {
+                lowest[l] = map.cells[i];
+                basin.low_point = (x, y);
+            }
+            heights[l] += map.cells[i] as usize;
+
+            let inside = map
+                .neighbours(i, Connectivity::Four)
+                .filter(|&n| self.labels[n] == label)
+                .count();
+            basin.perimeter += 4 - inside;
+            for n in map.neighbours(i, self.options.connectivity) {
+                if self.labels[n] == WALL {
+                    rims[l] = Some(rims[l].map_or(map.cells[n], |r: u8| r.min(map.cells[n])));
+                }
+            }
+        }
+
+        for (l, basin) in basins.iter_mut().enumerate() {
+            basin.rim = rims[l].unwrap_or(self.options.wall);
+            basin.depth = basin.rim - lowest[l];
+            basin.volume = basin.rim as usize * basin.area - heights[l];
+        }
+        basins
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    // Breadth first reference labelling.
+    fn reference(map: &Heightmap, options: Options) -> Vec<u32> {
+        let mut labels = vec![WALL; map.cells.len()];
+        let mut next = 0;
+        for start in 0..map.cells.len() {
+            if map.cells[start] >= options.wall || labels[start] != WALL {
+                continue;
+            }
+            labels[start] = next;
+            let mut queue = VecDeque::from([start]);
+            while let Some(i) = queue.pop_front() {
+                for n in map.neighbours(i, options.connectivity) {
+                    if map.cells[n] < options.wall && labels[n] == WALL {
+                        labels[n] = next;
+                        queue.push_back(n);
+                    }
+                }
+            }
+            next += 1;
+        }
+        labels
+    }
+
+    #[test]
+    fn test_matches_flood_fill() {
+        let mut seed = 21u64;
+        let mut next = |m: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % m) as u8
+        };
+
+        for round in 0..50 {
+            let (width, height) = (1 + round % 13, 1 + round % 7 * 3);
+            let map = Heightmap {
+                width,
+                height,
+                cells: (0..width * height)
+                    .map(|_| if next(3) == 0 { 9 } else { next(9) })
+                    .collect(),
+            };
+            let options = Options {
+                wall: [9, 7, 5][round % 3],
+                connectivity: [Connectivity::Four, Connectivity::Eight][round % 2],
+            };
+            let basins = BasinMap::new(&map, options);
+            assert_eq!(reference(&map, options), basins.labels);
+            for (label, &size) in basins.sizes.iter().enumerate() {
+                let count = basins.labels.iter().filter(|&&l| l == label as u32).count();
+                assert_eq!(count, size);
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_basin() {
+        // One basin winding through the whole grid, which a recursive fill
+        // can't follow without overflowing the stack.
+        let (width, height) = (2000, 2000);
+        let cells = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                match (y % 2, y % 4, x) {
+                    (0, _, _) => 1,
+                    (_, 1, x) if x == width - 1 => 1,
+                    (_, 3, 0) => 1,
+                    _ => 9,
+                }
+            })
+            .collect();
+        let map = Heightmap {
+            width,
+            height,
+            cells,
+        };
+        let basins = BasinMap::new(&map, Options::default());
+        assert_eq!(vec![width * height / 2 + height / 2], basins.sizes);
+    }
+
+    #[test]
+    fn test_describe() {
+        let map = crate::generate(
+            "2199943210
+            3987894921
+            9856789892
+            8767896789
+            9899965678",
+        );
+        let basins = BasinMap::new(&map, Options::default());
+        let described = basins.describe(&map);
+        assert_eq!(
+            Basin {
+                area: 3,
+                perimeter: 8,
+                bbox: ((0, 0), (1, 1)),
+                low_point: (1, 0),
+                rim: 9,
+                depth: 8,
+                volume: 21,
+            },
+            described[0]
+        );
+        assert_eq!(
+            vec![3, 9, 14, 9],
+            described.iter().map(|b| b.area).collect::<Vec<_>>()
+        );
+
+        let options = Options {
+            wall: 8,
+            connectivity: Connectivity::Eight,
+        };
+        let described = BasinMap::new(&map, options).describe(&map);
+        assert!(described.iter().all(|b| b.rim >= 8));
+        assert!(described
+            .iter()
+            .all(|b| b.depth == b.rim - map.cells[b.low_point.1 * map.width + b.low_point.0]));
+    }
+}
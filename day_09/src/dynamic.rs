@@ -0,0 +1,389 @@
+use crate::basins::{BasinMap, Options, WALL};
+use crate::{Connectivity, Heightmap};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+// A heightmap open to edits, keeping the low points and basins of the
+// current heights. Basins are relabelled only around the edited cell: a new
+// wall splits its basin by searching all sides at once until just one is
+// left unfinished, and a removed wall merges basins into the largest one.
+pub struct Terrain {
+    map: Heightmap,
+    options: Options,
+    labels: Vec<u32>,
+    // Size of every basin id, 0 for ids that were freed.
+    sizes: Vec<usize>,
+    free: Vec<u32>,
+    ranking: BTreeSet<(usize, u32)>,
+    low: Vec<bool>,
+    risk: i64,
+}
+
+impl Terrain {
+    pub fn new(map: Heightmap, options: Options) -> Terrain {
+        let basins = BasinMap::new(&map, options);
+        let ranking = basins
+            .sizes
+            .iter()
+            .enumerate()
+            .map(|(id, &size)| (size, id as u32))
+            .collect();
+        let mut terrain = Terrain {
+            low: vec![false; map.cells.len()],
+            map,
+            options,
+            labels: basins.labels,
+            sizes: basins.sizes,
+            free: Vec::new(),
+            ranking,
+            risk: 0,
+        };
+        (0..terrain.map.cells.len()).for_each(|i| terrain.mark_low(i));
+        terrain
+    }
+
+    // As in `get_minimas`, whatever the wall height.
+    fn is_low(&self, i: usize) -> bool {
+        self.map
+            .neighbours(i, Connectivity::Four)
+            .all(|n| self.map.cells[n] > self.map.cells[i])
+    }
+
+    fn mark_low(&mut self, i: usize) {
+        self.low[i] = self.is_low(i);
+        if self.low[i] {
+            self.risk += self.map.cells[i] as i64 + 1;
+        }
+    }
+
+    fn unmark_low(&mut self, i: usize) {
+        if self.low[i] {
+            self.risk -= self.map.cells[i] as i64 + 1;
+            self.low[i] = false;
+        }
+    }
+
+    fn resize(&mut self, id: u32, size: usize) {
+        self.ranking.remove(&(self.sizes[id as usize], id));
+        self.sizes[id as usize] = size;
+        match size {
+            0 => self.free.push(id),
+            _ => {
+                self.ranking.insert((size, id));
+            }
+        }
+    }
+
+    fn allocate(&mut self, size: usize) -> u32 {
+        let id = self.free.pop().unwrap_or_else(|| {
+            self.sizes.push(0);
+            self.sizes.len() as u32 - 1
+        });
+        self.resize(id, size);
+        id
+    }
+
+    // Relabels the connected cells of basin `from` reachable from `start`.
+    fn relabel(&mut self, start: usize, from: u32, to: u32) {
+        self.labels[start] = to;
+        let mut queue = VecDeque::from([start]);
+        while let Some(i) = queue.pop_front() {
+            for n in self.map.neighbours(i, self.options.connectivity) {
+                if self.labels[n] == from {
+                    self.labels[n] = to;
+                    queue.push_back(n);
+                }
+            }
+        }
+    }
+
+    fn merge(&mut self, i: usize) {
+        let mut touching = HashMap::new();
+        for n in self.map.neighbours(i, self.options.connectivity) {
+            if self.labels[n] != WALL {
+                touching.entry(self.labels[n]).or_insert(n);
+            }
+        }
+        let kept = touching
+            .keys()
+            .max_by_key(|&&id| (self.sizes[id as usize], id))
+            .cloned();
+        let kept = match kept {
+            Some(kept) => kept,
+            None => {
+                self.labels[i] = self.allocate(1);
+                return;
+            }
+        };
+
+        let mut size = self.sizes[kept as usize] + 1;
+        self.labels[i] = kept;
+        for (id, start) in touching {
+            if id != kept {
+                size += self.sizes[id as usize];
+                self.relabel(start, id, kept);
+                self.resize(id, 0);
+            }
+        }
+        self.resize(kept, size);
+    }
+
+    fn split(&mut self, i: usize, old: u32) {
+        let mut starts = self
+            .map
+            .neighbours(i, self.options.connectivity)
+            .filter(|&n| self.labels[n] == old)
+            .collect::<Vec<usize>>();
+        starts.sort_unstable();
+        if starts.len() < 2 {
+            let size = self.sizes[old as usize] - 1;
+            return self.resize(old, size);
+        }
+
+        // One breadth first search per side, stepped in turns. Searches that
+        // meet join the same group, and a group whose searches all ran out
+        // is a whole piece.
+        let mut group = (0..starts.len()).collect::<Vec<usize>>();
+        let root = |group: &[usize], mut s: usize| {
+            while group[s] != s {
+                s = group[s];
+            }
+            s
+        };
+        let mut owner = HashMap::new();
+        let mut queues = starts
+            .iter()
+            .enumerate()
+            .map(|(s, &start)| {
+                owner.insert(start, s);
+                VecDeque::from([start])
+            })
+            .collect::<Vec<VecDeque<usize>>>();
+
+        loop {
+            let open = (0..starts.len())
+                .filter(|&s| !queues[s].is_empty())
+                .map(|s| root(&group, s))
+                .collect::<BTreeSet<usize>>();
+            if open.len() < 2 {
+                break;
+            }
+            for (s, queue) in queues.iter_mut().enumerate() {
+                let Some(cell) = queue.pop_front() else {
+                    continue;
+                };
+                for n in self.map.neighbours(cell, self.options.connectivity) {
+                    if self.labels[n] != old {
+                        continue;
+                    }
+                    match owner.get(&n) {
+                        Some(&other) => {
+                            let (a, b) = (root(&group, s), root(&group, other));
+                            group[a.max(b)] = a.min(b);
+                        }
+                        None => {
+                            owner.insert(n, s);
+                            queue.push_back(n);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut pieces: HashMap<usize, usize> = HashMap::new();
+        let mut finished: HashMap<usize, bool> = HashMap::new();
+        for (s, queue) in queues.iter().enumerate() {
+            let g = root(&group, s);
+            *finished.entry(g).or_insert(true) &= queue.is_empty();
+        }
+        for &s in owner.values() {
+            *pieces.entry(root(&group, s)).or_insert(0) += 1;
+        }
+        // The unfinished side, or the largest one, keeps the old id.
+        let kept = *finished
+            .keys()
+            .max_by_key(|&&g| (!finished[&g], pieces[&g], usize::MAX - g))
+            .unwrap();
+
+        let mut size = self.sizes[old as usize] - 1;
+        let mut groups = finished.keys().cloned().collect::<Vec<usize>>();
+        groups.sort_unstable();
+        for g in groups {
+            if g != kept {
+                let id = self.allocate(pieces[&g]);
+                self.relabel(starts[g], old, id);
+                size -= pieces[&g];
+            }
+        }
+        self.resize(old, size);
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, height: u8) -> Result<(), String> {
+        if row >= self.map.height || col >= self.map.width {
+            return Err(format!("Cell {},{} outside the map", row, col));
+        }
+        let i = row * self.map.width + col;
+        let mut around = self
+            .map
+            .neighbours(i, Connectivity::Four)
+            .collect::<Vec<usize>>();
+        around.push(i);
+        around.iter().for_each(|&n| self.unmark_low(n));
+
+        let was_wall = self.labels[i] == WALL;
+        self.map.cells[i] = height;
+        match (was_wall, height >= self.options.wall) {
+            (true, false) => self.merge(i),
+            (false, true) => {
+                let old = self.labels[i];
+                self.labels[i] = WALL;
+                self.split(i, old);
+            }
+            _ => (),
+        }
+
+        around.iter().for_each(|&n| self.mark_low(n));
+        Ok(())
+    }
+
+    // Sum of the low points' risk levels, as in part 1.
+    pub fn risk(&self) -> i64 {
+        self.risk
+    }
+
+    // Product of the `n` largest basin sizes, as in part 2.
+    pub fn largest_product(&self, n: usize) -> usize {
+        self.ranking
+            .iter()
+            .rev()
+            .take(n)
+            .map(|&(size, _)| size)
+            .product()
+    }
+
+    pub fn low_points(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.low.len())
+            .filter(|&i| self.low[i])
+            .map(|i| (i / self.map.width, i % self.map.width))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Compares against labelling from scratch, up to the basin ids.
+    fn check(terrain: &Terrain) {
+        let basins = BasinMap::new(&terrain.map, terrain.options);
+        let mut ids = HashMap::new();
+        for (&a, &b) in basins.labels.iter().zip(&terrain.labels) {
+            assert_eq!(a == WALL, b == WALL);
+            assert_eq!(b, *ids.entry(a).or_insert(b));
+        }
+        let distinct = ids.values().collect::<BTreeSet<_>>();
+        assert_eq!(ids.len(), distinct.len());
+        for (&a, &b) in ids.iter().filter(|(&a, _)| a != WALL) {
+            assert_eq!(basins.sizes[a as usize], terrain.sizes[b as usize]);
+        }
+        assert_eq!(
+            basins.sizes.len(),
+            terrain.sizes.iter().filter(|&&s| s > 0).count()
+        );
+
+        let mut sizes = basins.sizes.clone();
+        sizes.sort_unstable();
+        assert_eq!(
+            sizes.iter().rev().take(3).product::<usize>(),
+            terrain.largest_product(3)
+        );
+        let risk = crate::get_minimas(&terrain.map)
+            .iter()
+            .map(|&i| terrain.map.cells[i] as i64 + 1)
+            .sum::<i64>();
+        assert_eq!(risk, terrain.risk());
+    }
+
+    #[test]
+    fn test_edits_match_recompute() {
+        let mut seed = 8u64;
+        let mut next = |m: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % m) as usize
+        };
+
+        for round in 0..20 {
+            let (width, height) = (4 + round % 9, 3 + round % 5);
+            let map = Heightmap {
+                width,
+                height,
+                cells: (0..width * height).map(|_| next(10) as u8).collect(),
+            };
+            let options = Options {
+                wall: [9, 7, 5][round % 3],
+                connectivity: [Connectivity::Four, Connectivity::Eight][round % 2],
+            };
+            let mut terrain = Terrain::new(map, options);
+            check(&terrain);
+            for _ in 0..100 {
+                let value = match next(2) {
+                    0 => 9,
+                    _ => next(9) as u8,
+                };
+                terrain
+                    .set(next(height as u64), next(width as u64), value)
+                    .unwrap();
+                check(&terrain);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sample_edits() {
+        let map = crate::generate(
+            "2199943210
+            3987894921
+            9856789892
+            8767896789
+            9899965678",
+        );
+        let mut terrain = Terrain::new(map, Options::default());
+        assert_eq!(15, terrain.risk());
+        assert_eq!(1134, terrain.largest_product(3));
+
+        // Opening the wall next to the top left basin joins it with the
+        // middle one.
+        terrain.set(0, 2, 5).unwrap();
+        assert_eq!(3 + 14 + 1, terrain.ranking.iter().next_back().unwrap().0);
+        terrain.set(0, 2, 9).unwrap();
+        assert_eq!(1134, terrain.largest_product(3));
+        assert_eq!(
+            vec![(0, 1), (0, 9), (2, 2), (4, 6)],
+            terrain.low_points().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_low_point_on_wall() {
+        let map = crate::generate("666\n656\n666");
+        let options = Options {
+            wall: 5,
+            connectivity: Connectivity::Four,
+        };
+        let terrain = Terrain::new(map, options);
+        assert_eq!(crate::part_1(&terrain.map) as i64, terrain.risk());
+        assert_eq!(6, terrain.risk());
+    }
+
+    #[test]
+    fn test_set_outside() {
+        let map = crate::generate("2199943210\n3987894921");
+        let mut terrain = Terrain::new(map, Options::default());
+        assert_eq!(
+            Err("Cell 0,10 outside the map".to_string()),
+            terrain.set(0, 10, 0)
+        );
+        assert!(terrain.set(2, 0, 0).is_err());
+        assert_eq!(Ok(()), terrain.set(1, 9, 0));
+    }
+}
@@ -0,0 +1,193 @@
+use crate::basins::label;
+use crate::Heightmap;
+use std::borrow::Cow;
+
+// Heights over any number of axes, the first one running fastest. Only cells
+// one step along a single axis are neighbours. A flat heightmap lends its
+// cells rather than copying them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid<'a> {
+    pub shape: Vec<usize>,
+    pub cells: Cow<'a, [u8]>,
+}
+
+impl<'a> From<&'a Heightmap> for Grid<'a> {
+    fn from(map: &'a Heightmap) -> Grid<'a> {
+        Grid {
+            shape: vec![map.width, map.height],
+            cells: Cow::Borrowed(&map.cells),
+        }
+    }
+}
+
+impl Grid<'_> {
+    // Rows of digits as in the puzzle, with one blank line between the
+    // slices of a third axis, two between the blocks of a fourth and so on.
+    pub fn parse(input: &str) -> Result<Grid<'static>, String> {
+        let mut rows = Vec::new();
+        let mut blanks = 0;
+        for line in input.lines().map(|l| l.trim()) {
+            match line.is_empty() {
+                true => blanks += 1,
+                false => {
+                    // Leading blank lines don't separate anything.
+                    rows.push((if rows.is_empty() { 0 } else { blanks }, line));
+                    blanks = 0;
+                }
+            }
+        }
+        let width = rows.first().ok_or("Empty grid")?.1.len();
+        let axes = 2 + rows.iter().map(|&(sep, _)| sep).max().unwrap();
+
+        // Each axis is as long as the run before its first separator.
+        let mut shape = vec![width];
+        let mut block = 1;
+        for sep in 1..axes - 1 {
+            let first = rows
+                .iter()
+                .position(|&(s, _)| s >= sep)
+                .unwrap_or(rows.len());
+            shape.push(first / block);
+            block = first;
+        }
+        shape.push(rows.len() / block);
+
+        for (r, &(sep, row)) in rows.iter().enumerate() {
+            let expected = (1..axes - 1)
+                .take_while(|&k| r % shape[1..=k].iter().product::<usize>() == 0)
+                .last()
+                .unwrap_or(0);
+            if row.len() != width || (r > 0 && sep != expected) {
+                return Err(format!("Irregular grid at row {}: {}", r + 1, row));
+            }
+        }
+        if rows.len() % block != 0 {
+            return Err("Irregular grid: last slice is incomplete".to_string());
+        }
+
+        let cells = rows
+            .iter()
+            .flat_map(|(_, row)| row.chars())
+            .map(|c| {
+                c.to_digit(10)
+                    .map(|d| d as u8)
+                    .ok_or(format!("Not a digit: {}", c))
+            })
+            .collect::<Result<Vec<u8>, String>>()?;
+        Ok(Grid {
+            shape,
+            cells: Cow::Owned(cells),
+        })
+    }
+
+    pub fn neighbours(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut stride = 1;
+        self.shape.iter().flat_map(move |&len| {
+            let step = stride;
+            stride *= len;
+            let c = i / step % len;
+            let down = (c > 0).then(|| i - step);
+            let up = (c + 1 < len).then_some(i + step);
+            down.into_iter().chain(up)
+        })
+    }
+
+    // Cells lower than all of their neighbours.
+    pub fn low_points(&self) -> Vec<usize> {
+        (0..self.cells.len())
+            .filter(|&i| self.neighbours(i).all(|n| self.cells[n] > self.cells[i]))
+            .collect()
+    }
+
+    // Basin sizes in order of their first cell, cells at least `wall` high
+    // separating them, labelled as in `BasinMap`.
+    pub fn basins(&self, wall: u8) -> Vec<usize> {
+        label(
+            self.cells.len(),
+            |i| self.cells[i] >= wall,
+            |i| self.neighbours(i),
+        )
+        .1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basins::{BasinMap, Options};
+    use crate::Connectivity;
+
+    #[test]
+    fn test_flat_grid_matches_heightmap() {
+        let sample = "2199943210
+            3987894921
+            9856789892
+            8767896789
+            9899965678";
+        let grid = Grid::parse(sample).unwrap();
+        assert_eq!(Grid::from(&crate::generate(sample)), grid);
+        assert_eq!(
+            15,
+            grid.low_points()
+                .iter()
+                .map(|&i| grid.cells[i] as i32 + 1)
+                .sum::<i32>()
+        );
+        assert_eq!(vec![3, 9, 14, 9], grid.basins(9));
+
+        let mut seed = 13u64;
+        let mut next = |m: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % m) as u8
+        };
+        for round in 0..30 {
+            let (width, height) = (1 + round % 9, 1 + round % 7);
+            let map = Heightmap {
+                width,
+                height,
+                cells: (0..width * height).map(|_| next(10)).collect(),
+            };
+            let grid = Grid::from(&map);
+            assert!(matches!(grid.cells, Cow::Borrowed(_)));
+            let minimas = (0..map.cells.len())
+                .filter(|&i| {
+                    map.neighbours(i, Connectivity::Four)
+                        .all(|n| map.cells[n] > map.cells[i])
+                })
+                .collect::<Vec<usize>>();
+            assert_eq!(minimas, grid.low_points());
+            assert_eq!(
+                BasinMap::new(&map, Options::default()).sizes,
+                grid.basins(9)
+            );
+        }
+    }
+
+    #[test]
+    fn test_voxels() {
+        // Two slices of 3x2, with a pit above a dip in the second one.
+        let grid = Grid::parse("\n555\n515\n\n555\n525\n").unwrap();
+        assert_eq!(vec![3, 2, 2], grid.shape);
+        assert_eq!(vec![4], grid.low_points());
+        assert_eq!(vec![12], grid.basins(9));
+        assert_eq!(vec![2], grid.basins(3));
+        assert_eq!(
+            vec![3, 5, 1, 10],
+            grid.neighbours(4).collect::<Vec<usize>>()
+        );
+
+        // A 4d grid of 2x1x2x2, a single pit at its last cell.
+        let grid = Grid::parse("99\n\n98\n\n\n98\n\n70").unwrap();
+        assert_eq!(vec![2, 1, 2, 2], grid.shape);
+        assert_eq!(vec![7], grid.low_points());
+        assert_eq!(vec![4], grid.basins(9));
+
+        assert!(Grid::parse("12\n3").is_err());
+        assert!(Grid::parse("12\n\n34\n56").is_err());
+        assert!(Grid::parse("12\n34\n\n56").is_err());
+        assert!(Grid::parse("1a").is_err());
+        assert!(Grid::parse("\n\n").is_err());
+    }
+}
@@ -1,93 +1,96 @@
+mod basins;
+mod dynamic;
+mod layered;
+mod water;
+
+use basins::{BasinMap, Options};
+use std::cmp::Reverse;
+use std::env;
 use std::fs;
 use std::time::Instant;
 
-type Generated = Vec<Vec<Cell>>;
+type Generated = Heightmap;
 
-#[derive(PartialEq, PartialOrd, Clone, Copy)]
-enum Cell {
-    Flooded,
-    Dry(i32),
+pub struct Heightmap {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<u8>,
 }
 
-fn generate(input: &str) -> Generated {
-    input
-        .lines()
-        .map(|l| l.trim())
-        .filter(|l| !l.is_empty())
-        .map(|l| l.chars().map(|c| Cell::Dry(c as i32 - 48)).collect())
-        .collect()
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Connectivity {
+    Four,
+    Eight,
 }
 
-fn get_minimas(input: &Generated) -> Vec<(usize, usize)> {
-    let mut minimas: Vec<(usize, usize)> = Vec::new();
-    for x in 0..input[0].len() as i32 {
-        for y in 0..input.len() as i32 {
-            let mut points: Vec<Cell> = Vec::new();
-            let val = input[y as usize][x as usize];
-
-            if x - 1 >= 0 {
-                points.push(input[y as usize][x as usize - 1]);
-            }
-            if x + 1 < input[0].len() as i32 {
-                points.push(input[y as usize][x as usize + 1]);
-            }
-            if y - 1 >= 0 {
-                points.push(input[y as usize - 1][x as usize]);
-            }
-            if y + 1 < input.len() as i32 {
-                points.push(input[y as usize + 1][x as usize]);
-            }
+impl Heightmap {
+    pub fn neighbours(&self, i: usize, connectivity: Connectivity) -> impl Iterator<Item = usize> {
+        let (x, y) = (i % self.width, i / self.width);
+        let (width, height) = (self.width, self.height);
+        let offsets: &[(isize, isize)] = match connectivity {
+            Connectivity::Four => &[(-1, 0), (1, 0), (0, -1), (0, 1)],
+            Connectivity::Eight => &[
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ],
+        };
+        offsets.iter().filter_map(move |&(dx, dy)| {
+            let x = x.checked_add_signed(dx).filter(|&x| x < width)?;
+            let y = y.checked_add_signed(dy).filter(|&y| y < height)?;
+            Some(y * width + x)
+        })
+    }
 
-            if points.iter().any(|p| p <= &val) {
-                continue;
-            } else {
-                minimas.push((y as usize, x as usize));
-            }
-        }
+    // Cells lower than all of their neighbours under the connectivity.
+    pub fn low_points(&self, connectivity: Connectivity) -> Vec<usize> {
+        (0..self.cells.len())
+            .filter(|&i| {
+                self.neighbours(i, connectivity)
+                    .all(|n| self.cells[n] > self.cells[i])
+            })
+            .collect()
     }
-    minimas
 }
 
-fn part_1(input: &Generated) -> i32 {
-    let minimas = get_minimas(input);
+fn generate(input: &str) -> Generated {
+    let lines = input
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<&str>>();
+    Heightmap {
+        width: lines[0].len(),
+        height: lines.len(),
+        cells: lines
+            .iter()
+            .flat_map(|l| l.bytes().map(|b| b - b'0'))
+            .collect(),
+    }
+}
 
-    minimas.iter().map(|m| match input[m.0][m.1] {
-        Cell::Dry(v) => v,
-        _ => 0,
-        } + 1).sum()
+// Cells lower than all of their neighbours, through the N-dimensional grid
+// the puzzle's map is the flat case of.
+pub fn get_minimas(input: &Generated) -> Vec<usize> {
+    layered::Grid::from(input).low_points()
 }
 
-fn flood_fill(input: &mut Generated, start: &(usize, usize)) -> usize {
-    match input[start.0][start.1] {
-        Cell::Dry(v) if v != 9 => {
-            input[start.0][start.1] = Cell::Flooded;
-            let mut result = 1;
-            if start.0 as i32 - 1 >= 0 {
-                result += flood_fill(input, &(start.0 - 1, start.1));
-            }
-            if start.0 + 1 < input.len() {
-                result += flood_fill(input, &(start.0 + 1, start.1));
-            }
-            if start.1 as i32 - 1 >= 0 {
-                result += flood_fill(input, &(start.0, start.1 - 1));
-            }
-            if start.1 + 1 < input[0].len() {
-                result += flood_fill(input, &(start.0, start.1 + 1));
-            }
-            result
-        },
-        _ => 0,
-    }
+fn part_1(input: &Generated) -> i32 {
+    get_minimas(input)
+        .iter()
+        .map(|&i| input.cells[i] as i32 + 1)
+        .sum()
 }
 
 fn part_2(input: &Generated) -> usize {
-    let minimas = get_minimas(input);
-    let mut results = minimas
-        .iter()
-        .map(|position| flood_fill(&mut input.clone(), position))
-        .collect::<Vec<usize>>();
-    results.sort_unstable();
-    results.iter().rev().take(3).product()
+    let mut sizes = layered::Grid::from(input).basins(Options::default().wall);
+    sizes.sort_unstable();
+    sizes.iter().rev().take(3).product()
 }
 
 fn main() {
@@ -113,6 +116,105 @@ fn main() {
         res2,
         res2_stop.duration_since(res2_start)
     );
+
+    // e.g. `--basins 5 --wall 8 --diagonal`, `--edit 3,4,9 --edit 0,2,5`,
+    // `--flood 7 --merge-tree`, `--voxels depths.txt`
+    let args = env::args().collect::<Vec<String>>();
+    let arg = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .map(|pos| args.get(pos + 1).expect("Missing argument value").as_str())
+    };
+    let options = Options {
+        wall: arg("--wall").map_or(9, |v| v.parse().unwrap()),
+        connectivity: match args.iter().any(|a| a == "--diagonal") {
+            true => Connectivity::Eight,
+            false => Connectivity::Four,
+        },
+    };
+    if let Some(count) = arg("--basins") {
+        let mut basins = BasinMap::new(&data, options).describe(&data);
+        basins.sort_by_key(|b| Reverse(b.area));
+        for basin in basins.iter().take(count.parse().unwrap()) {
+            println!("{:?}", basin);
+        }
+    }
+
+    if let Some(level) = arg("--flood") {
+        let level = level.parse().unwrap();
+        let mut lakes = water::flood(&data, level, options.connectivity);
+        println!(
+            "Level {}: {} lakes holding {}",
+            level,
+            lakes.len(),
+            lakes.iter().map(|l| l.volume).sum::<usize>()
+        );
+        lakes.sort_by_key(|l| Reverse(l.volume));
+        for lake in lakes.iter().take(3) {
+            println!("{:?}", lake);
+        }
+    }
+    if args.iter().any(|a| a == "--merge-tree") {
+        // Cut at the flood level if there is one.
+        let tree = water::MergeTree::new(&data, options.connectivity);
+        let tops = match arg("--flood") {
+            Some(level) => tree.lakes(level.parse().unwrap()),
+            None => tree.roots().collect(),
+        };
+        println!("{} lakes from {} leaves", tops.len(), tree.leaves().count());
+        for top in tops {
+            tree.print(&data, top, 0);
+        }
+    }
+
+    if let Some(path) = arg("--voxels") {
+        let grid = layered::Grid::parse(&fs::read_to_string(path).expect("file not found"))
+            .expect("Incorrect grid");
+        let risk = grid
+            .low_points()
+            .iter()
+            .map(|&i| grid.cells[i] as i32 + 1)
+            .sum::<i32>();
+        let mut sizes = grid.basins(options.wall);
+        sizes.sort_unstable();
+        println!(
+            "Grid {:?}: risk {}, largest basins {}",
+            grid.shape,
+            risk,
+            sizes.iter().rev().take(3).product::<usize>()
+        );
+    }
+
+    let edits = args
+        .windows(2)
+        .filter(|w| w[0] == "--edit")
+        .map(|w| {
+            let v = w[1]
+                .split(',')
+                .map(|v| v.trim().parse::<usize>().ok())
+                .collect::<Option<Vec<usize>>>();
+            match v.as_deref() {
+                Some(&[row, col, height]) if height <= 9 => Ok((row, col, height as u8)),
+                _ => Err(format!("Expected row,col,height up to 9: {}", w[1])),
+            }
+        })
+        .collect::<Result<Vec<(usize, usize, u8)>, String>>()
+        .expect("Incorrect edit");
+    if !edits.is_empty() {
+        let mut terrain = dynamic::Terrain::new(data, options);
+        for (row, col, height) in edits {
+            terrain.set(row, col, height).expect("Incorrect edit");
+            println!(
+                "After setting {},{} to {}: risk {}, largest basins {}, {} low points",
+                row,
+                col,
+                height,
+                terrain.risk(),
+                terrain.largest_product(3),
+                terrain.low_points().count()
+            );
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,311 @@
+use crate::basins::find;
+use crate::{Connectivity, Heightmap};
+use std::collections::{BTreeMap, VecDeque};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lake {
+    // Low points under the lake, in reading order, with the neighbours of the
+    // flood's connectivity.
+    pub low_points: Vec<usize>,
+    // The first lowest cell in reading order.
+    pub deepest: usize,
+    pub area: usize,
+    pub depth: u16,
+    pub volume: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    // Water level at which the lake appears, or its children join.
+    pub level: u16,
+    pub deepest: usize,
+    // For a leaf, the low point it forms around, with the neighbours of the
+    // tree's connectivity. A flat bottom of several cells has none, and
+    // neither do the joins.
+    pub low_point: Option<usize>,
+    pub children: Vec<usize>,
+    pub parent: Option<usize>,
+}
+
+// Lakes as the water rises: leaves are the lakes that form around the low
+// points, or around flat bottoms lower than everything next to them, and
+// every other node is where some of them join into one.
+pub struct MergeTree {
+    pub nodes: Vec<Node>,
+}
+
+// Rain keeps falling until the water stands at `level`. The map edge holds
+// water, so every cell below the level ends up submerged and the lakes are
+// the connected groups of such cells.
+pub fn flood(map: &Heightmap, level: u16, connectivity: Connectivity) -> Vec<Lake> {
+    let minimas = map.low_points(connectivity);
+    let mut seen = vec![false; map.cells.len()];
+    let mut lakes = Vec::new();
+
+    for start in 0..map.cells.len() {
+        if seen[start] || map.cells[start] as u16 >= level {
+            continue;
+        }
+        seen[start] = true;
+        let mut cells = vec![start];
+        let mut queue = VecDeque::from([start]);
+        while let Some(i) = queue.pop_front() {
+            for n in map.neighbours(i, connectivity) {
+                if !seen[n] && (map.cells[n] as u16) < level {
+                    seen[n] = true;
+                    cells.push(n);
+                    queue.push_back(n);
+                }
+            }
+        }
+
+        cells.sort_unstable();
+        let deepest = *cells.iter().min_by_key(|&&i| (map.cells[i], i)).unwrap();
+        lakes.push(Lake {
+            low_points: minimas
+                .iter()
+                .filter(|i| cells.binary_search(i).is_ok())
+                .cloned()
+                .collect(),
+            deepest,
+            area: cells.len(),
+            depth: level - map.cells[deepest] as u16,
+            volume: cells
+                .iter()
+                .map(|&i| (level - map.cells[i] as u16) as usize)
+                .sum(),
+        });
+    }
+    lakes
+}
+
+impl MergeTree {
+    // Adds the cells one height at a time, joining them to the submerged
+    // neighbours. After each height, the lakes that now share a root become
+    // children of a new node, and a root without any is a new leaf.
+    pub fn new(map: &Heightmap, connectivity: Connectivity) -> MergeTree {
+        let minimas = map.low_points(connectivity);
+        let mut by_height = vec![Vec::new(); 256];
+        for (i, &h) in map.cells.iter().enumerate() {
+            by_height[h as usize].push(i);
+        }
+        let mut parent = (0..map.cells.len() as u32).collect::<Vec<u32>>();
+        let mut submerged = vec![false; map.cells.len()];
+        let mut nodes: Vec<Node> = Vec::new();
+        // A cell of every current lake, with its node.
+        let mut lakes: Vec<(usize, usize)> = Vec::new();
+
+        for (h, cells) in by_height.iter().enumerate() {
+            if cells.is_empty() {
+                continue;
+            }
+            let level = h as u16 + 1;
+            for &i in cells {
+                submerged[i] = true;
+                for n in map.neighbours(i, connectivity) {
+                    if submerged[n] {
+                        let (a, b) = (find(&mut parent, i as u32), find(&mut parent, n as u32));
+                        parent[a.max(b) as usize] = a.min(b);
+                    }
+                }
+            }
+
+            let mut groups: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+            let mut firsts = BTreeMap::new();
+            for &(cell, node) in &lakes {
+                groups
+                    .entry(find(&mut parent, cell as u32))
+                    .or_default()
+                    .push(node);
+            }
+            for &i in cells {
+                let root = find(&mut parent, i as u32);
+                groups.entry(root).or_default();
+                firsts.entry(root).or_insert(i);
+            }
+
+            lakes.clear();
+            for (root, children) in groups {
+                let node = match children.len() {
+                    1 => children[0],
+                    _ => {
+                        let deepest = children
+                            .iter()
+                            .map(|&c| nodes[c].deepest)
+                            .min_by_key(|&i| (map.cells[i], i))
+                            .unwrap_or(firsts[&root]);
+                        let low_point = match children.is_empty() {
+                            true => minimas.binary_search(&deepest).ok().map(|_| deepest),
+                            false => None,
+                        };
+                        for &c in &children {
+                            nodes[c].parent = Some(nodes.len());
+                        }
+                        nodes.push(Node {
+                            level,
+                            deepest,
+                            low_point,
+                            children,
+                            parent: None,
+                        });
+                        nodes.len() - 1
+                    }
+                };
+                lakes.push((root as usize, node));
+            }
+        }
+
+        MergeTree { nodes }
+    }
+
+    pub fn roots(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.nodes.len()).filter(|&n| self.nodes[n].parent.is_none())
+    }
+
+    pub fn leaves(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter().filter(|n| n.children.is_empty())
+    }
+
+    // The nodes standing for the lakes at `level`.
+    pub fn lakes(&self, level: u16) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|&n| self.nodes[n].level <= level)
+            .filter(|&n| {
+                self.nodes[n]
+                    .parent
+                    .is_none_or(|p| self.nodes[p].level > level)
+            })
+            .collect()
+    }
+
+    pub fn print(&self, map: &Heightmap, node: usize, indent: usize) {
+        let n = &self.nodes[node];
+        println!(
+            "{:indent$}level {} at {},{}",
+            "",
+            n.level,
+            n.deepest / map.width,
+            n.deepest % map.width,
+            indent = indent
+        );
+        for &c in &n.children {
+            self.print(map, c, indent + 2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flood_sample() {
+        let map = crate::generate(
+            "2199943210
+            3987894921
+            9856789892
+            8767896789
+            9899965678",
+        );
+        let lake = |low_points: Vec<usize>, deepest, area, depth, volume| Lake {
+            low_points,
+            deepest,
+            area,
+            depth,
+            volume,
+        };
+        assert_eq!(
+            vec![lake(vec![1], 1, 1, 1, 1), lake(vec![9], 9, 3, 2, 4)],
+            flood(&map, 2, Connectivity::Four)
+        );
+
+        // Just below the walls the lakes are the basins.
+        let lakes = flood(&map, 9, Connectivity::Four);
+        assert_eq!(
+            vec![3, 9, 14, 9],
+            lakes.iter().map(|l| l.area).collect::<Vec<_>>()
+        );
+        assert!(flood(&map, 0, Connectivity::Four).is_empty());
+        assert_eq!(1, flood(&map, 10, Connectivity::Four).len());
+
+        let tree = MergeTree::new(&map, Connectivity::Four);
+        let mut leaves = tree.leaves().map(|n| n.low_point).collect::<Vec<_>>();
+        leaves.sort_unstable();
+        let minimas = crate::get_minimas(&map);
+        assert_eq!(minimas.into_iter().map(Some).collect::<Vec<_>>(), leaves);
+        assert_eq!(
+            vec![10],
+            tree.roots()
+                .map(|r| tree.nodes[r].level)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_tree_matches_flood() {
+        let mut seed = 5u64;
+        let mut next = |m: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % m) as u8
+        };
+
+        for round in 0..30 {
+            // Few heights in every other pair of rounds, leaving plateaus.
+            let (width, height) = (1 + round % 11, 1 + round % 6 * 2);
+            let heights = [10, 10, 3, 3][round % 4];
+            let map = Heightmap {
+                width,
+                height,
+                cells: (0..width * height).map(|_| next(heights)).collect(),
+            };
+            let connectivity = [Connectivity::Four, Connectivity::Eight][round % 2];
+            let tree = MergeTree::new(&map, connectivity);
+            for level in 0..=11 {
+                let mut expected = flood(&map, level, connectivity)
+                    .iter()
+                    .map(|l| l.deepest)
+                    .collect::<Vec<_>>();
+                let mut lakes = tree
+                    .lakes(level)
+                    .iter()
+                    .map(|&n| tree.nodes[n].deepest)
+                    .collect::<Vec<_>>();
+                expected.sort_unstable();
+                lakes.sort_unstable();
+                assert_eq!(expected, lakes);
+            }
+            for node in &tree.nodes {
+                assert_ne!(1, node.children.len());
+            }
+            let mut low_points = tree
+                .leaves()
+                .filter_map(|n| n.low_point)
+                .collect::<Vec<_>>();
+            low_points.sort_unstable();
+            assert_eq!(map.low_points(connectivity), low_points);
+            if connectivity == Connectivity::Four {
+                assert_eq!(crate::get_minimas(&map), low_points);
+            }
+        }
+
+        // A flat bottom is a leaf without a low point.
+        let map = crate::generate("00\n99");
+        let tree = MergeTree::new(&map, Connectivity::Four);
+        assert_eq!(
+            vec![None],
+            tree.leaves().map(|n| n.low_point).collect::<Vec<_>>()
+        );
+        assert_eq!(0, tree.nodes[tree.lakes(1)[0]].deepest);
+
+        // Diagonally, the 1 drains into the 0 and is no low point.
+        let map = crate::generate("19\n90");
+        let tree = MergeTree::new(&map, Connectivity::Eight);
+        assert_eq!(
+            vec![Some(3)],
+            tree.leaves().map(|n| n.low_point).collect::<Vec<_>>()
+        );
+        assert_eq!(vec![0, 3], crate::get_minimas(&map));
+    }
+}